@@ -0,0 +1,72 @@
+//! Compares the sweep-and-prune broad phase against testing every pair.
+//!
+//! Run with `cargo bench --bench broadphase`.
+
+use flappy::broadphase::BroadPhase;
+use flappy::collision::{self, Collider, ShapeKind};
+use flappy::game;
+use flappy::scene::Scene;
+use flappy::spawner::PipeSpawner;
+use nalgebra::Translation3;
+use std::time::{Duration, Instant};
+
+/// An endless-mode course: `pipes` pipe pairs spaced along the scroll axis,
+/// with a cloud of bird-sized particles scattered across it.
+fn build_scene(pipes: usize, particles: usize) -> Scene {
+    let mut scene = Scene::new();
+    game::spawn_bounds(&mut scene);
+    let gap = PipeSpawner::default().gap;
+    for i in 0..pipes {
+        let entity = scene.spawn();
+        let mut mesh = game::pipe_pair_mesh(gap);
+        mesh.translation = Translation3::new(i as f32 * 120.0, 0.0, ((i * 37) % 60) as f32 - 30.0);
+        entity.insert(&mut scene, Collider::from_mesh_quads(&mesh, ShapeKind::Aabb));
+        entity.add_mesh(&mut scene, mesh);
+    }
+    let width = pipes as f32 * 120.0;
+    // cheap deterministic scatter so runs are comparable
+    let mut seed: u32 = 0x2545_f491;
+    let mut next = || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+    for _ in 0..particles {
+        let entity = scene.spawn();
+        let mut mesh = game::bird_mesh();
+        mesh.translation = Translation3::new(next() * width, 0.0, next() * 256.0 - 128.0);
+        entity.insert(&mut scene, Collider::from_mesh(&mesh, ShapeKind::Circle));
+        entity.add_mesh(&mut scene, mesh);
+    }
+    scene
+}
+
+fn time(broad_phase: BroadPhase, bounds: &[(flappy::entity::Entity, collision::Bounds)]) -> (Duration, usize) {
+    let iterations = 20;
+    let mut contacts = 0;
+    let start = Instant::now();
+    for _ in 0..iterations {
+        contacts = collision::find_contacts(bounds, broad_phase).len();
+    }
+    (start.elapsed() / iterations, contacts)
+}
+
+fn main() {
+    println!("{:>6} {:>10} {:>14} {:>14} {:>8}", "pipes", "particles", "brute force", "sweep+prune", "speedup");
+    for (pipes, particles) in [(4, 0), (16, 64), (64, 256), (128, 1024), (256, 4096)] {
+        let mut scene = build_scene(pipes, particles);
+        let bounds = collision::collect_bounds(&mut scene);
+        let (naive, naive_contacts) = time(BroadPhase::BruteForce, &bounds);
+        let (sap, sap_contacts) = time(BroadPhase::SweepAndPrune, &bounds);
+        assert_eq!(naive_contacts, sap_contacts, "broad phases disagree");
+        println!(
+            "{:>6} {:>10} {:>14?} {:>14?} {:>7.1}x",
+            pipes,
+            particles,
+            naive,
+            sap,
+            naive.as_secs_f64() / sap.as_secs_f64()
+        );
+    }
+}
@@ -1,4 +1,4 @@
-#version 460 core
+#version 450 core
 layout (location = 0) in vec3 i_pos;
 layout (location = 1) in vec3 i_col;
 layout (location = 2) in vec2 i_uv;
@@ -9,6 +9,7 @@ out vec2 o_uv;
 out vec3 o_normal;
 
 uniform mat4 iRot;
+uniform mat4 model;
 uniform mat4 view;
 uniform mat4 cam;
 
@@ -18,6 +19,6 @@ void main()
     o_col =  i_col;
     o_uv = i_uv;
     o_normal = i_normal;
-    gl_Position = cam * view * vec4(i_pos, 1.0);
+    gl_Position = cam * view * model * vec4(i_pos, 1.0);
     //gl_Position = vec4(i_pos, 1.0);
 }
@@ -0,0 +1,18 @@
+#version 450 core
+out vec4 FragColor;
+
+in vec2 o_uv;
+
+uniform sampler2D tex;
+uniform vec2 resolution;
+// Pixels red and blue are pulled apart by at the edges of the screen.
+uniform float offset;
+
+void main()
+{
+    vec2 shift = (o_uv - 0.5) * 2.0 * offset / resolution;
+    vec4 color = texture(tex, o_uv);
+    float r = texture(tex, o_uv + shift).r;
+    float b = texture(tex, o_uv - shift).b;
+    FragColor = vec4(r, color.g, b, color.a);
+}
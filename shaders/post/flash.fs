@@ -0,0 +1,15 @@
+#version 450 core
+out vec4 FragColor;
+
+in vec2 o_uv;
+
+uniform sampler2D tex;
+// How far the screen is washed out to `color`, from 0 to 1.
+uniform float amount;
+uniform vec3 color;
+
+void main()
+{
+    vec4 scene = texture(tex, o_uv);
+    FragColor = vec4(mix(scene.rgb, color, amount), scene.a);
+}
@@ -0,0 +1,10 @@
+#version 450 core
+out vec2 o_uv;
+
+// One triangle covering the screen, drawn with no vertex buffer.
+void main()
+{
+    vec2 corner = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    o_uv = corner;
+    gl_Position = vec4(corner * 2.0 - 1.0, 0.0, 1.0);
+}
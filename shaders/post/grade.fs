@@ -0,0 +1,36 @@
+#version 450 core
+out vec4 FragColor;
+
+in vec2 o_uv;
+
+uniform sampler2D tex;
+// A 256x16 colour lookup table: 16 slices of 16x16 side by side, blue
+// choosing the slice, red running across it and green down it.
+uniform sampler2D lut;
+// How far to move toward the graded colour, from 0 to 1.
+uniform float strength;
+
+vec3 lookup(float slice, vec2 rg)
+{
+    // interpolate red and green by hand, so the table needn't be filtered
+    vec2 pos = rg * 15.0;
+    ivec2 cell = ivec2(floor(pos));
+    ivec2 next = min(cell + 1, 15);
+    vec2 t = pos - vec2(cell);
+    int x = int(slice) * 16;
+    vec3 a = texelFetch(lut, ivec2(x + cell.x, cell.y), 0).rgb;
+    vec3 b = texelFetch(lut, ivec2(x + next.x, cell.y), 0).rgb;
+    vec3 c = texelFetch(lut, ivec2(x + cell.x, next.y), 0).rgb;
+    vec3 d = texelFetch(lut, ivec2(x + next.x, next.y), 0).rgb;
+    return mix(mix(a, b, t.x), mix(c, d, t.x), t.y);
+}
+
+void main()
+{
+    vec4 color = texture(tex, o_uv);
+    float blue = clamp(color.b, 0.0, 1.0) * 15.0;
+    float slice = floor(blue);
+    vec2 rg = clamp(color.rg, 0.0, 1.0);
+    vec3 graded = mix(lookup(slice, rg), lookup(min(slice + 1.0, 15.0), rg), blue - slice);
+    FragColor = vec4(mix(color.rgb, graded, strength), color.a);
+}
@@ -0,0 +1,17 @@
+#version 450 core
+out vec4 FragColor;
+
+in vec2 o_uv;
+
+uniform sampler2D tex;
+// How much darker the gaps between lines are, from 0 to 1.
+uniform float intensity;
+// Pixels from one line to the next.
+uniform float spacing;
+
+void main()
+{
+    vec4 color = texture(tex, o_uv);
+    float gap = step(0.5, fract(gl_FragCoord.y / spacing));
+    FragColor = vec4(color.rgb * (1.0 - gap * intensity), color.a);
+}
@@ -0,0 +1,21 @@
+#version 450 core
+out vec4 FragColor;
+
+in vec2 o_uv;
+
+uniform sampler2D tex;
+uniform vec2 resolution;
+// How dark the corners get, from 0 to 1.
+uniform float strength;
+// Distance from the centre, as a fraction of half the diagonal, where the
+// darkening starts, and how far it takes to reach full strength.
+uniform float radius;
+uniform float softness;
+
+void main()
+{
+    vec4 color = texture(tex, o_uv);
+    float dist = length(o_uv - 0.5) / length(vec2(0.5));
+    float shade = smoothstep(radius, radius + softness, dist);
+    FragColor = vec4(color.rgb * (1.0 - shade * strength), color.a);
+}
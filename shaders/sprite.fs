@@ -0,0 +1,16 @@
+#version 450 core
+out vec4 FragColor;
+
+in vec2 o_uv;
+in vec4 o_tint;
+
+uniform sampler2D tex;
+
+void main()
+{
+    vec4 tex_color = texture(tex, o_uv) * o_tint;
+    if (tex_color.a < 0.1) {
+        discard;
+    }
+    FragColor = tex_color;
+}
@@ -0,0 +1,19 @@
+#version 450 core
+layout (location = 0) in vec3 i_pos;
+layout (location = 2) in vec2 i_uv;
+// per instance
+layout (location = 4) in mat4 i_model;
+layout (location = 8) in vec4 i_uv_rect;
+layout (location = 9) in vec4 i_tint;
+out vec2 o_uv;
+out vec4 o_tint;
+
+uniform mat4 view;
+uniform mat4 cam;
+
+void main()
+{
+    o_uv = mix(i_uv_rect.xy, i_uv_rect.zw, i_uv);
+    o_tint = i_tint;
+    gl_Position = cam * view * i_model * vec4(i_pos, 1.0);
+}
@@ -0,0 +1,167 @@
+use crate::components::Mesh;
+use crate::entity::Entity;
+use crate::renderer::texture::{Atlas, Region};
+use crate::scene::Scene;
+use crate::time::Time;
+use std::fmt;
+
+/// What an `Animation` does after its last frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlayMode {
+    /// Start again from the first frame.
+    #[default]
+    Loop,
+    /// Play backwards to the first frame, then forwards again.
+    PingPong,
+    /// Stop on the last frame and report it in `AnimationEvents`.
+    Once,
+}
+
+/// Component cycling its entity's `Mesh` through named regions of an atlas.
+/// Every quad of the mesh shows the current frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    frames: Vec<String>,
+    /// Where each of `frames` is in the atlas, looked up once when built.
+    regions: Vec<Region>,
+    /// Frames per second.
+    pub fps: f32,
+    pub mode: PlayMode,
+    pub playing: bool,
+    /// Seconds played since the animation started.
+    elapsed: f32,
+    finished: bool,
+}
+
+impl Animation {
+    /// An animation through the regions of `atlas` called `frames`.
+    pub fn new<S: Into<String>>(
+        atlas: &Atlas,
+        frames: impl IntoIterator<Item = S>,
+        fps: f32,
+        mode: PlayMode,
+    ) -> Result<Self, AnimationError> {
+        let frames: Vec<String> = frames.into_iter().map(Into::into).collect();
+        if frames.is_empty() {
+            return Err(AnimationError::NoFrames);
+        }
+        let regions = frames
+            .iter()
+            .map(|name| atlas.region(name).copied().ok_or_else(|| AnimationError::UnknownFrame(name.clone())))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            frames,
+            regions,
+            fps,
+            mode,
+            playing: true,
+            elapsed: 0.0,
+            finished: false,
+        })
+    }
+    /// Names of the frames, in order.
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+    /// Index into `frames` of the frame showing now.
+    pub fn current_frame(&self) -> usize {
+        let n = self.frames.len();
+        let step = (self.elapsed * self.fps) as usize;
+        match self.mode {
+            PlayMode::Loop => step % n,
+            PlayMode::PingPong if n > 1 => {
+                let period = 2 * n - 2;
+                let k = step % period;
+                if k < n {
+                    k
+                } else {
+                    period - k
+                }
+            }
+            PlayMode::PingPong => 0,
+            PlayMode::Once => step.min(n - 1),
+        }
+    }
+    pub fn current(&self) -> &str {
+        &self.frames[self.current_frame()]
+    }
+    pub fn current_region(&self) -> &Region {
+        &self.regions[self.current_frame()]
+    }
+    /// Whether a one-shot animation has played its last frame out.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    /// Play from the first frame again.
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.playing = true;
+    }
+    /// Move `dt` seconds on. Returns true on the tick a one-shot animation
+    /// finishes.
+    pub fn advance(&mut self, dt: f32) -> bool {
+        if !self.playing || self.finished {
+            return false;
+        }
+        self.elapsed += dt;
+        if self.mode == PlayMode::Once && self.elapsed * self.fps >= self.frames.len() as f32 {
+            self.finished = true;
+            return true;
+        }
+        false
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnimationError {
+    NoFrames,
+    /// A frame name the atlas has no region for.
+    UnknownFrame(String),
+}
+
+impl fmt::Display for AnimationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnimationError::NoFrames => write!(f, "an animation needs at least one frame"),
+            AnimationError::UnknownFrame(name) => write!(f, "no region `{}` in the atlas", name),
+        }
+    }
+}
+
+impl std::error::Error for AnimationError {}
+
+/// One-shot animations that finished during the most recent `animate` pass.
+#[derive(Debug, Default)]
+pub struct AnimationEvents {
+    pub finished: Vec<Entity>,
+}
+
+impl AnimationEvents {
+    pub fn new() -> Self {
+        Self { finished: Vec::new() }
+    }
+}
+
+/// Advance every `Animation` by the frame time and point its mesh's UVs at the
+/// current frame. Replaces the scene's `AnimationEvents`.
+pub fn animate(scene: &mut Scene) {
+    let dt = scene.resource::<Time>().map_or(0.0, Time::delta_secs);
+    let mut finished = Vec::new();
+    for (entity, animation, mesh) in scene.query::<(Entity, &mut Animation, &mut Mesh)>() {
+        if animation.advance(dt) {
+            finished.push(entity);
+        }
+        let uvs = animation.current_region().uvs();
+        // only touch the mesh when the frame changes, so it isn't re-uploaded
+        if mesh.verts().chunks(4).all(|quad| quad.iter().zip(uvs).all(|(vert, uv)| vert.uv == uv)) {
+            continue;
+        }
+        for quad in mesh.verts_mut().chunks_mut(4) {
+            for (vert, uv) in quad.iter_mut().zip(uvs) {
+                vert.uv = uv;
+            }
+        }
+    }
+    scene.insert_resource(AnimationEvents { finished });
+}
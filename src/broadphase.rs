@@ -0,0 +1,55 @@
+use crate::collision::Bounds;
+use crate::entity::Entity;
+
+/// Strategy for picking which pairs of shapes the narrow phase should test.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BroadPhase {
+    /// Every pair; O(n²), kept as a reference.
+    BruteForce,
+    /// Sort shapes by their left edge along the scroll axis (x) and only pair
+    /// shapes whose x intervals overlap.
+    #[default]
+    SweepAndPrune,
+}
+
+impl BroadPhase {
+    /// Index pairs `(i, j)` with `i < j` into `bounds` that may overlap.
+    pub fn candidate_pairs(&self, bounds: &[(Entity, Bounds)]) -> Vec<(usize, usize)> {
+        match self {
+            BroadPhase::BruteForce => brute_force(bounds),
+            BroadPhase::SweepAndPrune => sweep_and_prune(bounds),
+        }
+    }
+}
+
+fn brute_force(bounds: &[(Entity, Bounds)]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..bounds.len() {
+        for j in i + 1..bounds.len() {
+            pairs.push((i, j));
+        }
+    }
+    pairs
+}
+
+fn sweep_and_prune(bounds: &[(Entity, Bounds)]) -> Vec<(usize, usize)> {
+    let aabbs: Vec<_> = bounds.iter().map(|(_, b)| b.aabb()).collect();
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    order.sort_unstable_by(|&a, &b| aabbs[a].0.x.total_cmp(&aabbs[b].0.x));
+
+    let mut pairs = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for &i in &order {
+        let (min, max) = aabbs[i];
+        // anything that ends before this shape starts can never overlap later ones
+        active.retain(|&j| aabbs[j].1.x >= min.x);
+        for &j in &active {
+            let (other_min, other_max) = aabbs[j];
+            if other_min.y <= max.y && min.y <= other_max.y {
+                pairs.push((i.min(j), i.max(j)));
+            }
+        }
+        active.push(i);
+    }
+    pairs
+}
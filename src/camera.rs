@@ -7,6 +7,8 @@ pub struct Camera {
     perspective: Matrix4<f32>,
     width: u32,
     height: u32,
+    /// Twice the number of window pixels per world unit.
+    zoom: f32,
 }
 
 impl Camera {
@@ -20,6 +22,7 @@ impl Camera {
             perspective: Matrix4::new_orthographic(-4.0, 4.0, -4.0, 4.0, -0.01, 100.0),
             width: 1920,
             height: 1080,
+            zoom: 5.0,
         }
     }
     pub fn rotate_ver(&mut self, mut angle: f32) {
@@ -40,11 +43,23 @@ impl Camera {
     }
     pub fn resize(&mut self, width: u32, height: u32) {
         self.perspective = Matrix4::new_perspective((width as f32)/(height as f32), 90.0, 0.01, 1000.0);
-        let zoom = 5.0;
-            self.perspective = Matrix4::new_orthographic((width as f32 / -zoom), (width as f32 / zoom), (height as f32 / -zoom),(height as f32 / zoom), 0.01, 100.0);
+        let zoom = self.zoom;
+            self.perspective = Matrix4::new_orthographic(width as f32 / -zoom, width as f32 / zoom, height as f32 / -zoom, height as f32 / zoom, 0.01, 100.0);
         self.width = width;
         self.height = height;
     }
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+        self.resize(self.width, self.height);
+    }
+    /// Width and height of the window the camera fills, in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+    /// Half the width and height of the visible area, in world units.
+    pub fn half_extents(&self) -> (f32, f32) {
+        (self.width as f32 / self.zoom, self.height as f32 / self.zoom)
+    }
     pub fn view(&self) -> &Matrix4<f32> { 
         &self.view
     }
@@ -0,0 +1,332 @@
+use crate::broadphase::BroadPhase;
+use crate::components::Mesh;
+use crate::entity::Entity;
+use crate::scene::Scene;
+use nalgebra::{Point2, Point3, Vector2, Vector3};
+
+/// A collision shape in the local space of its entity's mesh, on the x/z plane
+/// the game is played on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Box that stays axis aligned in world space; a rotated mesh gets the
+    /// bounds of its rotated box.
+    Aabb {
+        center: Point2<f32>,
+        half_extents: Vector2<f32>,
+    },
+    /// Box that rotates with the mesh.
+    Obb {
+        center: Point2<f32>,
+        half_extents: Vector2<f32>,
+    },
+    Circle {
+        center: Point2<f32>,
+        radius: f32,
+    },
+}
+
+/// Which kind of `Shape` to fit when deriving a collider from mesh bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShapeKind {
+    Aabb,
+    Obb,
+    /// Circle inscribed in the bounds.
+    Circle,
+}
+
+impl Shape {
+    /// Fit a shape of `kind` to the x/z bounds of `verts`.
+    pub fn fit(kind: ShapeKind, verts: impl IntoIterator<Item = Point3<f32>>) -> Self {
+        let mut min = Point2::new(f32::MAX, f32::MAX);
+        let mut max = Point2::new(f32::MIN, f32::MIN);
+        for p in verts {
+            min = Point2::new(min.x.min(p.x), min.y.min(p.z));
+            max = Point2::new(max.x.max(p.x), max.y.max(p.z));
+        }
+        let center = nalgebra::center(&min, &max);
+        let half_extents = (max - min) / 2.0;
+        match kind {
+            ShapeKind::Aabb => Shape::Aabb { center, half_extents },
+            ShapeKind::Obb => Shape::Obb { center, half_extents },
+            ShapeKind::Circle => Shape::Circle {
+                center,
+                radius: half_extents.min(),
+            },
+        }
+    }
+}
+
+/// Collision component made of one or more shapes.
+///
+/// Shapes are placed by the entity's `Mesh` transform if it has one, and are in
+/// world space otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collider {
+    pub shapes: Vec<Shape>,
+}
+
+impl Collider {
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shapes: vec![shape],
+        }
+    }
+    /// A single shape covering the whole mesh.
+    pub fn from_mesh(mesh: &Mesh, kind: ShapeKind) -> Self {
+        Self::new(Shape::fit(kind, mesh.verts().iter().map(|v| v.pos)))
+    }
+    /// One shape per quad of the mesh, for meshes like the pipe pair that are
+    /// several disjoint sprites.
+    pub fn from_mesh_quads(mesh: &Mesh, kind: ShapeKind) -> Self {
+        Self {
+            shapes: mesh
+                .verts()
+                .chunks(4)
+                .map(|quad| Shape::fit(kind, quad.iter().map(|v| v.pos)))
+                .collect(),
+        }
+    }
+}
+
+/// A shape placed in the world, ready for overlap tests.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bounds {
+    /// Parallelogram spanned by the half-axes `u` and `v` around `center`.
+    /// Axis-aligned and oriented boxes both end up here.
+    Box {
+        center: Point2<f32>,
+        u: Vector2<f32>,
+        v: Vector2<f32>,
+    },
+    Circle {
+        center: Point2<f32>,
+        radius: f32,
+    },
+}
+
+fn plane(v: Vector3<f32>) -> Vector2<f32> {
+    Vector2::new(v.x, v.z)
+}
+
+impl Bounds {
+    /// Place `shape` with `mesh`'s transform, applied the same way as
+    /// `Mesh::verts_transformed`: rotation, then scale, then translation.
+    pub fn from_shape(shape: &Shape, mesh: Option<&Mesh>) -> Self {
+        let to_world_vector = |v: Vector2<f32>| -> Vector2<f32> {
+            match mesh {
+                Some(m) => plane(m.scale * (m.rotation * Vector3::new(v.x, 0.0, v.y))),
+                None => v,
+            }
+        };
+        let to_world_point = |p: Point2<f32>| -> Point2<f32> {
+            match mesh {
+                Some(m) => {
+                    let p = m.translation * (m.scale * (m.rotation * Point3::new(p.x, 0.0, p.y)));
+                    Point2::new(p.x, p.z)
+                }
+                None => p,
+            }
+        };
+        match *shape {
+            Shape::Obb { center, half_extents } => Bounds::Box {
+                center: to_world_point(center),
+                u: to_world_vector(Vector2::new(half_extents.x, 0.0)),
+                v: to_world_vector(Vector2::new(0.0, half_extents.y)),
+            },
+            Shape::Aabb { center, half_extents } => {
+                let u = to_world_vector(Vector2::new(half_extents.x, 0.0));
+                let v = to_world_vector(Vector2::new(0.0, half_extents.y));
+                Bounds::Box {
+                    center: to_world_point(center),
+                    u: Vector2::new(u.x.abs() + v.x.abs(), 0.0),
+                    v: Vector2::new(0.0, u.y.abs() + v.y.abs()),
+                }
+            }
+            Shape::Circle { center, radius } => {
+                let scale = mesh.map_or(1.0, |m| m.scale.vector.abs().max());
+                Bounds::Circle {
+                    center: to_world_point(center),
+                    radius: radius * scale,
+                }
+            }
+        }
+    }
+    pub fn center(&self) -> Point2<f32> {
+        match *self {
+            Bounds::Box { center, .. } | Bounds::Circle { center, .. } => center,
+        }
+    }
+    /// Axis-aligned `(min, max)` enclosing these bounds.
+    pub fn aabb(&self) -> (Point2<f32>, Point2<f32>) {
+        match *self {
+            Bounds::Box { center, u, v } => {
+                let extent = u.abs() + v.abs();
+                (center - extent, center + extent)
+            }
+            Bounds::Circle { center, radius } => {
+                let extent = Vector2::new(radius, radius);
+                (center - extent, center + extent)
+            }
+        }
+    }
+    /// Half-width of the projection of these bounds onto unit `axis`.
+    fn radius_along(&self, axis: &Vector2<f32>) -> f32 {
+        match self {
+            Bounds::Box { u, v, .. } => u.dot(axis).abs() + v.dot(axis).abs(),
+            Bounds::Circle { radius, .. } => *radius,
+        }
+    }
+    fn corners(&self) -> Option<[Point2<f32>; 4]> {
+        match *self {
+            Bounds::Box { center, u, v } => Some([
+                center + u + v,
+                center + u - v,
+                center - u - v,
+                center - u + v,
+            ]),
+            Bounds::Circle { .. } => None,
+        }
+    }
+    /// Edge normals of a box; none for a circle.
+    fn axes(&self) -> Vec<Vector2<f32>> {
+        match self {
+            Bounds::Box { u, v, .. } => [u, v]
+                .iter()
+                .filter_map(|a| Vector2::new(-a.y, a.x).try_normalize(f32::EPSILON))
+                .collect(),
+            Bounds::Circle { .. } => Vec::new(),
+        }
+    }
+    /// Separating axis test. On overlap returns the unit normal pointing from
+    /// `self` towards `other` and the penetration depth along it.
+    pub fn intersect(&self, other: &Bounds) -> Option<(Vector2<f32>, f32)> {
+        let offset = other.center() - self.center();
+        let mut axes = self.axes();
+        axes.extend(other.axes());
+        match (self, other) {
+            (Bounds::Circle { .. }, Bounds::Circle { .. }) => {
+                axes.push(offset.try_normalize(f32::EPSILON).unwrap_or(Vector2::y()));
+            }
+            (Bounds::Circle { center, .. }, b @ Bounds::Box { .. })
+            | (b @ Bounds::Box { .. }, Bounds::Circle { center, .. }) => {
+                // the only other candidate axis is towards the nearest corner
+                let nearest = b
+                    .corners()
+                    .unwrap()
+                    .into_iter()
+                    .min_by(|p, q| {
+                        (p - center)
+                            .norm_squared()
+                            .total_cmp(&(q - center).norm_squared())
+                    })
+                    .unwrap();
+                if let Some(axis) = (nearest - center).try_normalize(f32::EPSILON) {
+                    axes.push(axis);
+                }
+            }
+            _ => {}
+        }
+
+        let mut best: Option<(Vector2<f32>, f32)> = None;
+        for axis in axes {
+            let depth = self.radius_along(&axis) + other.radius_along(&axis) - offset.dot(&axis).abs();
+            if depth <= 0.0 {
+                return None;
+            }
+            if best.is_none_or(|(_, d)| depth < d) {
+                let normal = if offset.dot(&axis) < 0.0 { -axis } else { axis };
+                best = Some((normal, depth));
+            }
+        }
+        best
+    }
+}
+
+/// Two colliders overlapping during a collision pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    pub a: Entity,
+    pub b: Entity,
+    /// Unit normal pointing from `a` towards `b`.
+    pub normal: Vector2<f32>,
+    pub depth: f32,
+}
+
+impl Contact {
+    pub fn involves(&self, entity: Entity) -> bool {
+        self.a == entity || self.b == entity
+    }
+    /// The entity `entity` collided with, if this contact involves it.
+    pub fn other(&self, entity: Entity) -> Option<Entity> {
+        if self.a == entity {
+            Some(self.b)
+        } else if self.b == entity {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+/// Contacts found by the most recent `detect_collisions` pass.
+#[derive(Debug, Default)]
+pub struct CollisionEvents {
+    pub contacts: Vec<Contact>,
+}
+
+impl CollisionEvents {
+    pub fn new() -> Self {
+        Self {
+            contacts: Vec::new(),
+        }
+    }
+    pub fn involving(&self, entity: Entity) -> impl Iterator<Item = &Contact> {
+        self.contacts.iter().filter(move |c| c.involves(entity))
+    }
+}
+
+/// World-space bounds of every collider shape, tagged with its entity.
+pub fn collect_bounds(scene: &mut Scene) -> Vec<(Entity, Bounds)> {
+    let mut bounds = Vec::new();
+    for (entity, collider, mesh) in scene.query::<(Entity, &Collider, Option<&Mesh>)>() {
+        bounds.extend(
+            collider
+                .shapes
+                .iter()
+                .map(|shape| (entity, Bounds::from_shape(shape, mesh))),
+        );
+    }
+    bounds
+}
+
+/// Test one candidate pair of shapes, skipping shapes of the same entity.
+pub fn narrow_phase(a: &(Entity, Bounds), b: &(Entity, Bounds)) -> Option<Contact> {
+    if a.0 == b.0 {
+        return None;
+    }
+    let (normal, depth) = a.1.intersect(&b.1)?;
+    Some(Contact {
+        a: a.0,
+        b: b.0,
+        normal,
+        depth,
+    })
+}
+
+/// Find every overlapping pair of collider shapes and replace the scene's
+/// `CollisionEvents` with the contacts found. Candidate pairs come from the
+/// scene's `BroadPhase` resource, sweep-and-prune if there is none.
+pub fn detect_collisions(scene: &mut Scene) {
+    let broad_phase = scene.resource::<BroadPhase>().copied().unwrap_or_default();
+    let contacts = find_contacts(&collect_bounds(scene), broad_phase);
+    scene.insert_resource(CollisionEvents { contacts });
+}
+
+/// Run `broad_phase` over `bounds` and narrow-phase test its candidate pairs.
+pub fn find_contacts(bounds: &[(Entity, Bounds)], broad_phase: BroadPhase) -> Vec<Contact> {
+    broad_phase
+        .candidate_pairs(bounds)
+        .into_iter()
+        .filter_map(|(i, j)| narrow_phase(&bounds[i], &bounds[j]))
+        .collect()
+}
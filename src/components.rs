@@ -1,43 +1,143 @@
-use nalgebra::{Vector3, Translation3, Rotation3, Scale3};
+use nalgebra::{Matrix4, Vector2, Vector3, Translation3, Rotation3, Scale3};
 use crate::renderer::primatives::{Vert};
+use crate::renderer::texture::Region;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-pub enum Component {
-    Mesh(usize),
-    Velocity(usize),
-    Acceleration(usize),
-    RotVelocity(usize),
-    RotAcceleration(usize),
-}
+/// Source of `Mesh::generation`s, shared by every mesh so one replaced by
+/// another never looks unchanged.
+static GENERATIONS: AtomicU64 = AtomicU64::new(1);
 
+fn next_generation() -> u64 {
+    GENERATIONS.fetch_add(1, Ordering::Relaxed)
+}
 
 #[derive(Debug)]
 pub struct Mesh {
-    pub verts: Vec<Vert>,
-    pub elements: Vec<u32>,
+    verts: Vec<Vert>,
+    elements: Vec<u32>,
+    /// Changes whenever `verts` might have, so the renderer knows when to
+    /// upload them again.
+    generation: u64,
     pub translation: Translation3<f32>,
     pub rotation: Rotation3<f32>,
     pub scale: Scale3<f32>,
 }
 
 impl Mesh {
-    pub fn verts(&self) -> Vec<Vert> {
-        self.verts.clone()
+    /// A mesh of the triangles `elements` index out of `verts`, at the origin.
+    pub fn new(verts: Vec<Vert>, elements: Vec<u32>) -> Self {
+        Self {
+            verts,
+            elements,
+            generation: next_generation(),
+            translation: Translation3::identity(),
+            rotation: Rotation3::identity(),
+            scale: Scale3::identity(),
+        }
+    }
+
+    pub fn verts(&self) -> &[Vert] {
+        &self.verts
+    }
+
+    /// The vertices, for changing them; marks the mesh as changed.
+    pub fn verts_mut(&mut self) -> &mut [Vert] {
+        self.generation = next_generation();
+        &mut self.verts
+    }
+
+    pub fn elements(&self) -> &[u32] {
+        &self.elements
+    }
+
+    /// An id that differs from every other mesh's, and from this one's
+    /// before its vertices were last borrowed mutably.
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
 
     pub fn verts_transformed(&self) -> Vec<Vert> {
+        self.verts_transformed_by(&self.translation, &self.rotation, &self.scale)
+    }
+
+    /// Vertices in model space, with UVs flipped for GL the same way as
+    /// `verts_transformed`. The model matrix places them.
+    pub fn verts_local(&self) -> Vec<Vert> {
+        self.verts
+            .iter()
+            .map(|v| {
+                let mut v = *v;
+                v.uv.y = 1.0 - v.uv.y;
+                v
+            })
+            .collect()
+    }
+
+    /// The mesh's transform as a matrix.
+    pub fn model(&self) -> Matrix4<f32> {
+        model_matrix(&self.translation, &self.rotation, &self.scale)
+    }
+
+    /// Vertices placed with the given transform instead of the mesh's own.
+    pub fn verts_transformed_by(
+        &self,
+        translation: &Translation3<f32>,
+        rotation: &Rotation3<f32>,
+        scale: &Scale3<f32>,
+    ) -> Vec<Vert> {
         self.verts.clone()
             .into_iter()
             .map(|mut v| {
-                v.pos = self.rotation * v.pos;
-                v.pos = self.scale * v.pos;
-                v.pos = self.translation * v.pos;
-                v.uv.y =  -1.0 * v.uv.y + 1.0;
+                v.pos = rotation * v.pos;
+                v.pos = scale * v.pos;
+                v.pos = translation * v.pos;
+                v.uv.y = 1.0 - v.uv.y;
                 v
             })
             .collect()
     }
 }
 
+/// Matrix applying `rotation`, then `scale`, then `translation`, matching
+/// `Mesh::verts_transformed_by`.
+pub fn model_matrix(translation: &Translation3<f32>, rotation: &Rotation3<f32>, scale: &Scale3<f32>) -> Matrix4<f32> {
+    translation.to_homogeneous() * scale.to_homogeneous() * rotation.to_homogeneous()
+}
+
+/// A single atlas region drawn through the renderer's instanced sprite path
+/// instead of as a `Mesh`. Suited to the many identical quads of tiles and
+/// particles.
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    pub region: Region,
+    /// Size of the quad before `scale`, in world units; the region's pixel
+    /// size unless changed.
+    pub size: Vector2<f32>,
+    /// Colour the texture is multiplied by.
+    pub tint: [f32; 4],
+    pub translation: Translation3<f32>,
+    pub rotation: Rotation3<f32>,
+    pub scale: Scale3<f32>,
+}
+
+impl Sprite {
+    pub fn new(region: &Region) -> Self {
+        Self {
+            region: *region,
+            size: region.size(),
+            tint: [1.0; 4],
+            translation: Translation3::identity(),
+            rotation: Rotation3::identity(),
+            scale: Scale3::identity(),
+        }
+    }
+    /// Transform of a unit quad on the x/z plane into this sprite.
+    pub fn model(&self) -> Matrix4<f32> {
+        let size = Scale3::new(self.size.x, 1.0, self.size.y);
+        model_matrix(&self.translation, &self.rotation, &self.scale) * size.to_homogeneous()
+    }
+}
+
 #[derive(Debug)]
 pub struct Velocity {
     pub velocity: Vector3<f32>,
@@ -45,7 +145,7 @@ pub struct Velocity {
 impl Velocity {
     pub fn new(velocity: Vector3<f32>) -> Self {
         Self {
-            velocity: velocity,
+            velocity,
         }
     }
 }
@@ -60,7 +160,7 @@ pub struct RotVelocity {
 impl RotVelocity {
     pub fn new(velocity: Vector3<f32>) -> Self {
         Self {
-            velocity: velocity,
+            velocity,
         }
     }
 }
@@ -0,0 +1,318 @@
+use crate::renderer::post::{Effect, Param};
+use crate::scene::Scene;
+use crate::time::Time;
+use colored::Colorize;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How the bird handles.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BirdConfig {
+    /// Upward speed a flap sets, in units per second.
+    pub flap_velocity: f32,
+    /// Vertical acceleration, in units per second squared.
+    pub gravity: f32,
+    /// Pitch a flap snaps the bird to, in degrees; negative is nose up.
+    pub flap_pitch: f32,
+    /// How fast the bird tips nose down after a flap, in degrees per second
+    /// squared.
+    pub dive_acceleration: f32,
+    /// Furthest the bird may pitch either way, in degrees.
+    pub max_pitch: f32,
+}
+
+impl Default for BirdConfig {
+    fn default() -> Self {
+        Self {
+            flap_velocity: 425.0,
+            gravity: -1400.0,
+            flap_pitch: -45.0,
+            dive_acceleration: 360.0,
+            max_pitch: 90.0,
+        }
+    }
+}
+
+/// Height of the floor and ceiling the bird is kept between.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlayAreaConfig {
+    pub floor: f32,
+    pub ceiling: f32,
+}
+
+impl Default for PlayAreaConfig {
+    fn default() -> Self {
+        Self {
+            floor: -128.0,
+            ceiling: 128.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraConfig {
+    /// Twice the number of window pixels per world unit.
+    pub zoom: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self { zoom: 5.0 }
+    }
+}
+
+/// How often the simulation ticks.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimestepConfig {
+    /// Fixed updates a second.
+    pub tick_rate: u32,
+    /// Most ticks run in one frame before the backlog is dropped.
+    pub max_steps: u32,
+}
+
+impl Default for TimestepConfig {
+    fn default() -> Self {
+        Self {
+            tick_rate: 120,
+            max_steps: 8,
+        }
+    }
+}
+
+/// Full-screen effects run over each frame.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostConfig {
+    /// In the order they run.
+    pub effects: Vec<Effect>,
+}
+
+/// Resource holding the game's tuning, read from `config/game.toml`. Missing
+/// entries keep their defaults.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub bird: BirdConfig,
+    pub play_area: PlayAreaConfig,
+    pub camera: CameraConfig,
+    pub timestep: TimestepConfig,
+    pub post: PostConfig,
+}
+
+impl GameConfig {
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(text).map_err(ConfigError::Parse)?;
+        config.validate()?;
+        Ok(config)
+    }
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::parse(&text)
+    }
+    /// Check values are in range, reporting every problem at once.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: String| {
+            if !ok {
+                problems.push(problem);
+            }
+        };
+        let bird = &self.bird;
+        let fields = [
+            ("bird.flap_velocity", bird.flap_velocity),
+            ("bird.gravity", bird.gravity),
+            ("bird.flap_pitch", bird.flap_pitch),
+            ("bird.dive_acceleration", bird.dive_acceleration),
+            ("bird.max_pitch", bird.max_pitch),
+            ("play_area.floor", self.play_area.floor),
+            ("play_area.ceiling", self.play_area.ceiling),
+            ("camera.zoom", self.camera.zoom),
+        ];
+        for (name, value) in fields {
+            check(value.is_finite(), format!("{} must be a finite number, got {}", name, value));
+        }
+        check(
+            bird.flap_velocity > 0.0,
+            format!("bird.flap_velocity must be positive, got {}", bird.flap_velocity),
+        );
+        check(
+            bird.gravity < 0.0,
+            format!("bird.gravity must be negative (downwards), got {}", bird.gravity),
+        );
+        check(
+            (0.0..=180.0).contains(&bird.max_pitch),
+            format!("bird.max_pitch must be between 0 and 180 degrees, got {}", bird.max_pitch),
+        );
+        check(
+            bird.flap_pitch.abs() <= bird.max_pitch,
+            format!(
+                "bird.flap_pitch ({}) must be within bird.max_pitch ({})",
+                bird.flap_pitch, bird.max_pitch
+            ),
+        );
+        check(
+            self.play_area.floor < self.play_area.ceiling,
+            format!(
+                "play_area.floor ({}) must be below play_area.ceiling ({})",
+                self.play_area.floor, self.play_area.ceiling
+            ),
+        );
+        check(
+            self.camera.zoom > 0.0,
+            format!("camera.zoom must be positive, got {}", self.camera.zoom),
+        );
+        check(
+            self.timestep.tick_rate > 0,
+            "timestep.tick_rate must be at least 1".to_string(),
+        );
+        check(
+            self.timestep.max_steps > 0,
+            "timestep.max_steps must be at least 1".to_string(),
+        );
+        let mut names = HashSet::new();
+        for effect in &self.post.effects {
+            let name = &effect.name;
+            check(names.insert(name), format!("post effect `{}` is listed more than once", name));
+            check(
+                effect.shader.is_file(),
+                format!("post effect `{}` has no shader at {}", name, effect.shader.display()),
+            );
+            for (param, value) in &effect.params {
+                match value {
+                    Param::Float(value) => check(
+                        value.is_finite(),
+                        format!("post effect `{}`: {} must be a finite number, got {}", name, param, value),
+                    ),
+                    Param::Vector(values) => check(
+                        (1..=4).contains(&values.len()) && values.iter().all(|v| v.is_finite()),
+                        format!("post effect `{}`: {} must be 1 to 4 finite numbers, got {:?}", name, param, values),
+                    ),
+                    Param::Texture(path) => check(
+                        path.is_file(),
+                        format!("post effect `{}`: {} names {}, which doesn't exist", name, param, path.display()),
+                    ),
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    /// Parsed, but some values are out of range.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "{}", e),
+            ConfigError::Parse(e) => write!(f, "{}", e),
+            ConfigError::Invalid(problems) => {
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Resource that watches the config file for changes.
+#[derive(Debug, Clone)]
+pub struct ConfigWatcher {
+    pub path: PathBuf,
+    /// How often the file's modification time is checked.
+    pub interval: Duration,
+    modified: Option<SystemTime>,
+    since_check: Duration,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            modified: modified(&path),
+            path,
+            interval: Duration::from_millis(500),
+            since_check: Duration::ZERO,
+        }
+    }
+    /// Load the watched file, printing what is wrong with it and falling back
+    /// to the defaults if it can't be used.
+    pub fn load(&self) -> GameConfig {
+        GameConfig::from_path(&self.path).unwrap_or_else(|e| {
+            report(&self.path, &e);
+            GameConfig::default()
+        })
+    }
+    /// Whether the file has changed since the last time this returned true.
+    fn poll(&mut self, dt: Duration) -> bool {
+        self.since_check += dt;
+        if self.since_check < self.interval {
+            return false;
+        }
+        self.since_check = Duration::ZERO;
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn report(path: &Path, e: &ConfigError) {
+    eprintln!("{} could not load {}:", "warning:".bold().red(), path.display());
+    for line in e.to_string().lines() {
+        eprintln!("  {}", line);
+    }
+}
+
+/// Reload the `GameConfig` when its file changes. A file that doesn't parse or
+/// validate is reported and the running config kept. Returns whether a new
+/// config was loaded.
+pub fn reload_config(scene: &mut Scene) -> bool {
+    let dt = scene.resource::<Time>().map_or(Duration::ZERO, |t| t.delta);
+    let Some(watcher) = scene.resource_mut::<ConfigWatcher>() else {
+        return false;
+    };
+    if !watcher.poll(dt) {
+        return false;
+    }
+    let path = watcher.path.clone();
+    match GameConfig::from_path(&path) {
+        Ok(config) => {
+            eprintln!("reloaded {}", path.display());
+            scene.insert_resource(config);
+            true
+        }
+        Err(e) => {
+            report(&path, &e);
+            false
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::components::Velocity;
+use crate::game::Pipe;
+use crate::scene::Scene;
+use crate::score::Score;
+use crate::spawner::PipeSpawner;
+use crate::time::Time;
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+/// Piecewise-linear curve through `(x, y)` points sorted by `x`. Flat before
+/// the first point and after the last.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "Vec<[f32; 2]>")]
+pub struct Curve {
+    points: Vec<[f32; 2]>,
+}
+
+impl Curve {
+    pub fn new(points: Vec<[f32; 2]>) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("a curve needs at least one point".to_string());
+        }
+        if points.iter().flatten().any(|v| !v.is_finite()) {
+            return Err("curve points must be finite".to_string());
+        }
+        if points.windows(2).any(|w| w[1][0] <= w[0][0]) {
+            return Err("curve points must be in increasing x order".to_string());
+        }
+        Ok(Self { points })
+    }
+    /// A curve that is `y` everywhere.
+    pub fn constant(y: f32) -> Self {
+        Self { points: vec![[0.0, y]] }
+    }
+    pub fn points(&self) -> &[[f32; 2]] {
+        &self.points
+    }
+    pub fn sample(&self, x: f32) -> f32 {
+        let i = self.points.partition_point(|p| p[0] <= x);
+        if i == 0 {
+            return self.points[0][1];
+        }
+        if i == self.points.len() {
+            return self.points[i - 1][1];
+        }
+        let ([x0, y0], [x1, y1]) = (self.points[i - 1], self.points[i]);
+        y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+    }
+}
+
+impl TryFrom<Vec<[f32; 2]>> for Curve {
+    type Error = String;
+    fn try_from(points: Vec<[f32; 2]>) -> Result<Self, String> {
+        Self::new(points)
+    }
+}
+
+/// What the difficulty curves are sampled by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Progress {
+    /// Pipes passed this round.
+    #[default]
+    Score,
+    /// Seconds since the round started.
+    Time,
+}
+
+/// Resource describing how the pipe course gets harder over a round.
+///
+/// Usually loaded from `config/difficulty.toml` so it can be tuned without
+/// recompiling; see that file for the format.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Difficulty {
+    #[serde(default)]
+    pub by: Progress,
+    /// Pipe scroll speed, in units per second.
+    pub speed: Curve,
+    /// Height of the opening in each pipe pair.
+    pub gap: Curve,
+    /// Horizontal distance between pipe pairs.
+    pub spacing: Curve,
+    /// How far above or below the middle a gap may be centred.
+    pub variance: Curve,
+    /// Seconds played this round.
+    #[serde(skip)]
+    elapsed: f32,
+}
+
+impl Difficulty {
+    /// Curves that hold the spawner's defaults for the whole round.
+    pub fn new() -> Self {
+        let spawner = PipeSpawner::default();
+        Self {
+            by: Progress::Score,
+            speed: Curve::constant(spawner.speed),
+            gap: Curve::constant(spawner.gap),
+            spacing: Curve::constant(spawner.spacing),
+            variance: Curve::constant(spawner.gap_center.1),
+            elapsed: 0.0,
+        }
+    }
+    pub fn parse(text: &str) -> Result<Self, DifficultyError> {
+        let difficulty: Self = toml::from_str(text).map_err(DifficultyError::Parse)?;
+        difficulty.validate()?;
+        Ok(difficulty)
+    }
+    pub fn from_path(path: &Path) -> Result<Self, DifficultyError> {
+        let text = std::fs::read_to_string(path).map_err(DifficultyError::Io)?;
+        Self::parse(&text)
+    }
+    /// Check the curves never leave the range the spawner can lay a course
+    /// with, reporting every problem at once. The curves are linear between
+    /// points, so checking the points covers everything in between.
+    pub fn validate(&self) -> Result<(), DifficultyError> {
+        let mut problems = Vec::new();
+        // speed and variance may be zero, for a still or flat course
+        let curves = [
+            ("speed", &self.speed, false),
+            ("gap", &self.gap, true),
+            ("spacing", &self.spacing, true),
+            ("variance", &self.variance, false),
+        ];
+        for (name, curve, positive) in curves {
+            for &[x, y] in curve.points() {
+                if positive && y <= 0.0 {
+                    problems.push(format!("{} must be positive, got {} at {}", name, y, x));
+                } else if y < 0.0 {
+                    problems.push(format!("{} must not be negative, got {} at {}", name, y, x));
+                }
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(DifficultyError::Invalid(problems))
+        }
+    }
+    /// Start measuring play time from zero.
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+    /// Point along the curves for a round at `score` after `elapsed` seconds.
+    pub fn progress(&self, score: u32, elapsed: f32) -> f32 {
+        match self.by {
+            Progress::Score => score as f32,
+            Progress::Time => elapsed,
+        }
+    }
+    /// Set the spawner's course parameters to the curves' values at `x`.
+    pub fn apply(&self, x: f32, spawner: &mut PipeSpawner) {
+        spawner.speed = self.speed.sample(x);
+        spawner.gap = self.gap.sample(x);
+        spawner.spacing = self.spacing.sample(x);
+        let variance = self.variance.sample(x);
+        spawner.gap_center = (-variance, variance);
+    }
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub enum DifficultyError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    /// Parsed, but some curves go out of range.
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for DifficultyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DifficultyError::Io(e) => write!(f, "{}", e),
+            DifficultyError::Parse(e) => write!(f, "{}", e),
+            DifficultyError::Invalid(problems) => {
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for DifficultyError {}
+
+/// Ramp the `PipeSpawner` along the `Difficulty` curves. Pipes already on
+/// screen are sped up with the new ones so the spacing between them holds.
+pub fn apply_difficulty(scene: &mut Scene) {
+    let dt = scene.resource::<Time>().map_or(0.0, Time::delta_secs);
+    let score = scene.resource::<Score>().map_or(0, |s| s.value);
+    let Some(difficulty) = scene.resource_mut::<Difficulty>() else {
+        return;
+    };
+    difficulty.elapsed += dt;
+    let x = difficulty.progress(score, difficulty.elapsed);
+
+    scene.resource_scope(|scene, difficulty: &mut Difficulty| {
+        let Some(spawner) = scene.resource_mut::<PipeSpawner>() else {
+            return;
+        };
+        difficulty.apply(x, spawner);
+        let speed = spawner.speed;
+        for velocity in scene.query::<&mut Velocity>().with::<Pipe>() {
+            velocity.velocity.x = -speed;
+        }
+    });
+}
@@ -1,108 +1,91 @@
-use crate::components::{Acceleration, Mesh, Velocity};
+use crate::components::{Acceleration, Mesh, RotAcceleration, RotVelocity, Velocity};
 use crate::scene::Scene;
 use nalgebra::Vector3;
 
-pub struct Entity{
-    pub mesh: Option<usize>,
-    pub velocity: Option<usize>,
-    pub acceleration: Option<usize>,
-    pub rot_velocity: Option<usize>,
-    pub rot_acceleration: Option<usize>,
+/// Generational handle to an entity living in a `Scene`.
+///
+/// Handles are created by `Scene::spawn` and stay cheap to copy around; once the
+/// entity is despawned every accessor on an old handle returns `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
 }
 
 impl Entity {
-    pub fn new() -> Self {
-        Self {
-            mesh: None,
-            velocity: None,
-            acceleration: None,
-            rot_velocity: None,
-            rot_acceleration: None,
-        }
-    }
-    pub fn add_mesh(&mut self, scene: &mut Scene, mesh: Mesh) {
-        scene.meshes.push(mesh);
-        self.mesh = Some(scene.meshes.len() - 1);
+    pub(crate) fn from_raw(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+    /// Attach any `'static` value to this entity as a component, returning the
+    /// component of the same type it replaced.
+    pub fn insert<T: 'static>(&self, scene: &mut Scene, component: T) -> Option<T> {
+        scene.insert(*self, component)
+    }
+    pub fn get<'a, T: 'static>(&self, scene: &'a Scene) -> Option<&'a T> {
+        scene.get::<T>(*self)
+    }
+    pub fn get_mut<'a, T: 'static>(&self, scene: &'a mut Scene) -> Option<&'a mut T> {
+        scene.get_mut::<T>(*self)
+    }
+    pub fn remove<T: 'static>(&self, scene: &mut Scene) -> Option<T> {
+        scene.remove::<T>(*self)
+    }
+    pub fn has<T: 'static>(&self, scene: &Scene) -> bool {
+        scene.has::<T>(*self)
+    }
+    /// Position of this entity's `T` in the scene's dense storage for `T`.
+    pub fn get_index<T: 'static>(&self, scene: &Scene) -> Option<usize> {
+        scene.storage::<T>()?.index_of(*self)
+    }
+    pub fn add_mesh(&self, scene: &mut Scene, mesh: Mesh) {
+        self.insert(scene, mesh);
     }
     pub fn get_mesh<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut Mesh> {
-        if let Some(idx) = self.mesh {
-            return Some(&mut scene.meshes[idx]);
-        }
-
-        None
+        self.get_mut(scene)
     }
-    pub fn get_mesh_index(&self) -> Option<usize> {
-        if let Some(idx) = self.mesh {
-            return Some(idx);
-        }
-        None
+    pub fn get_mesh_index(&self, scene: &Scene) -> Option<usize> {
+        self.get_index::<Mesh>(scene)
     }
-    pub fn add_velocity(&mut self, scene: &mut Scene, velocity: Vector3<f32>) {
-        let vel = Velocity {velocity: velocity};
-        scene.velocities.push(vel);
-        self.velocity = Some(scene.velocities.len() - 1);
+    pub fn add_velocity(&self, scene: &mut Scene, velocity: Vector3<f32>) {
+        self.insert(scene, Velocity::new(velocity));
     }
     pub fn get_velocity<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut Velocity> {
-        if let Some(idx) = self.velocity {
-            return Some(&mut scene.velocities[idx]);
-        }
-
-        None
-    }
-    pub fn get_velocity_index(&self) -> Option<usize> {
-        if let Some(idx) = self.velocity {
-            return Some(idx);
-        }
-        None
-    }
-    pub fn add_rot_velocity(&mut self, scene: &mut Scene, velocity: f32) {
-        scene.rot_velocities.push(velocity);
-        self.rot_velocity = Some(scene.velocities.len() - 1);
-    }
-    pub fn get_rot_velocity<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut f32> {
-        if let Some(idx) = self.rot_velocity {
-            return Some(&mut scene.rot_velocities[idx]);
-        }
-        None
-    }
-    pub fn get_rot_velocity_index(&self) -> Option<usize> {
-        if let Some(idx) = self.rot_velocity {
-            return Some(idx);
-        }
-        None
-    }
-    pub fn add_rot_acceleration(&mut self, scene: &mut Scene, acceleration: f32) {
-        scene.rot_accelerations.push(acceleration);
-        self.rot_acceleration = Some(scene.accelerations.len() - 1);
-    }
-    pub fn get_rot_acceleration<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut f32> {
-        if let Some(idx) = self.rot_acceleration {
-            return Some(&mut scene.rot_accelerations[idx]);
-        }
-        None
-    }
-    pub fn get_rot_acceleration_index(&self) -> Option<usize> {
-        if let Some(idx) = self.rot_acceleration {
-            return Some(idx);
-        }
-        None
-    }
-    pub fn add_acceleration(&mut self, scene: &mut Scene, acceleration: Vector3<f32>) {
-        let acc = Acceleration {acceleration: acceleration};
-        scene.accelerations.push(acc);
-        self.acceleration = Some(scene.accelerations.len() - 1);
+        self.get_mut(scene)
+    }
+    pub fn get_velocity_index(&self, scene: &Scene) -> Option<usize> {
+        self.get_index::<Velocity>(scene)
+    }
+    pub fn add_rot_velocity(&self, scene: &mut Scene, velocity: Vector3<f32>) {
+        self.insert(scene, RotVelocity::new(velocity));
+    }
+    pub fn get_rot_velocity<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut RotVelocity> {
+        self.get_mut(scene)
+    }
+    pub fn get_rot_velocity_index(&self, scene: &Scene) -> Option<usize> {
+        self.get_index::<RotVelocity>(scene)
+    }
+    pub fn add_rot_acceleration(&self, scene: &mut Scene, acceleration: Vector3<f32>) {
+        self.insert(scene, RotAcceleration { acceleration });
+    }
+    pub fn get_rot_acceleration<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut RotAcceleration> {
+        self.get_mut(scene)
+    }
+    pub fn get_rot_acceleration_index(&self, scene: &Scene) -> Option<usize> {
+        self.get_index::<RotAcceleration>(scene)
+    }
+    pub fn add_acceleration(&self, scene: &mut Scene, acceleration: Vector3<f32>) {
+        self.insert(scene, Acceleration { acceleration });
     }
     pub fn get_acceleration<'a>(&self, scene: &'a mut Scene) -> Option<&'a mut Acceleration> {
-        if let Some(idx) = self.acceleration {
-            return Some(&mut scene.accelerations[idx]);
-        }
-
-        None
+        self.get_mut(scene)
     }
-    pub fn get_acceleration_index(&self) -> Option<usize> {
-        if let Some(idx) = self.acceleration {
-            return Some(idx);
-        }
-        None
+    pub fn get_acceleration_index(&self, scene: &Scene) -> Option<usize> {
+        self.get_index::<Acceleration>(scene)
     }
 }
@@ -1,238 +1,715 @@
-use glutin;
-
-use glutin::surface::GlSurface;
-
+use crate::animation::{self, Animation, AnimationEvents, PlayMode};
 use crate::camera::Camera;
-use crate::components::Mesh;
+use crate::collision::{self, Collider, CollisionEvents, Shape, ShapeKind};
+use crate::components::{Acceleration, Mesh, RotAcceleration, RotVelocity, Sprite, Velocity};
+use crate::config::{self, ConfigWatcher, GameConfig};
+use crate::difficulty::{self, Difficulty};
 use crate::entity::Entity;
+use crate::image::Image;
 use crate::input::Keymap;
-use crate::renderer::primatives::{Cube, Quad, Vert};
-use crate::renderer::{Renderer, texture::Texture};
+use crate::physics::{self, Integrator};
+use crate::renderer::backend::{Backend, PipelineDesc, VertexLayout};
+use crate::renderer::layer::{Layer, RenderLayer};
+use crate::renderer::opengl::GlBackend;
+use crate::renderer::post::{Param, PostChain};
+use crate::renderer::primatives::{Quad, Vert};
+use crate::renderer::texture::{Atlas, Region};
+use crate::renderer::{Renderer, TransformMode};
+use crate::rng::Rng;
+use crate::save::SaveFile;
 use crate::scene::Scene;
-use crate::shader::Shader;
-use crate::windowing::Window;
-use nalgebra::{Point3, Point2, };
-use nalgebra::{Rotation3, Scale3, Translation3, Vector3};
-use winit::{
-    self,
-    event::{Event, WindowEvent},
-};
+use crate::schedule::{Schedule, Stage};
+use crate::score::{self, Score};
+use crate::spawner::{self, PipeSpawner};
+use crate::state::{self, GameState, State};
+use crate::time::Time;
+use crate::timestep::{FixedTimestep, PreviousTransform};
+use colored::Colorize;
+use nalgebra::{Point2, Point3, Rotation3, Scale3, Translation3, Vector2, Vector3};
+use std::sync::OnceLock;
+
+/// Marker for the bird.
+#[derive(Debug, Default)]
+pub struct Player;
+
+/// Marker for pipe pairs.
+#[derive(Debug, Default)]
+pub struct Pipe;
+
+/// Marker for the floor collider.
+#[derive(Debug, Default)]
+pub struct Ground;
+
+/// Marker for the ceiling collider.
+#[derive(Debug, Default)]
+pub struct Ceiling;
+
+/// Marker for the tiles of the scrolling ground strip.
+#[derive(Debug, Default)]
+pub struct GroundTile;
+
+/// Marker for title, get-ready and game-over sprites, despawned when their
+/// state is left.
+#[derive(Debug, Default)]
+pub struct Banner;
 
 pub struct Game {
     pub scene: Scene,
-    pub renderer: Renderer,
-    pub cam: Camera,
-    pub keymap: Keymap,
+    pub schedule: Schedule,
     pub player: Entity,
-    pub pipes: Entity,
-    pub shader: Shader,
-    pub sprite: Texture,
-    pub rot: f32,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
+    /// A game drawing with OpenGL on the current context, keeping scores in
+    /// the player's save.
     pub fn new() -> Self {
+        Self::with_backend(Box::new(GlBackend::new()), SaveFile::open_default())
+    }
+    /// A game drawing with `backend` and keeping scores in `save`.
+    pub fn with_backend(backend: Box<dyn Backend>, save: SaveFile) -> Self {
+        let mut scene = Scene::new();
+        let player = scene.spawn();
+
+        let mut renderer = Renderer::with_backend(backend);
+        let texture = Image::from_path(std::path::Path::new("textures/sprites.png"))
+            .unwrap_or_else(|e| panic!("could not load textures/sprites.png: {}", e));
+        let backend = renderer.backend_mut();
+        let texture = backend.create_texture(&texture);
+        let mesh_pipeline =
+            backend.create_pipeline(&PipelineDesc::new("shaders/hello.vs", "shaders/hello.fs", VertexLayout::Mesh));
+        let sprite_pipeline =
+            backend.create_pipeline(&PipelineDesc::new("shaders/sprite.vs", "shaders/sprite.fs", VertexLayout::Sprite));
+        renderer.texture = Some(texture);
+        renderer.mesh_pipeline = Some(mesh_pipeline);
+        renderer.sprite_pipeline = Some(sprite_pipeline);
+        renderer.transform_mode = TransformMode::Gpu;
+        scene.insert_resource(renderer);
+        let watcher = ConfigWatcher::new("config/game.toml");
+        let config = watcher.load();
+        let mut camera = Camera::new(Point3::new(0.0, 3.0, 0.0), Point3::new(0.0, 0.0, 0.0));
+        camera.set_zoom(config.camera.zoom);
+        scene.insert_resource(camera);
+        scene.insert_resource(PostChain::with_effects(config.post.effects.clone()));
+        let timestep = FixedTimestep::new(config.timestep.tick_rate)
+            .expect("tick rate checked by GameConfig::validate")
+            .with_max_steps(config.timestep.max_steps);
+        scene.insert_resource(timestep);
+        scene.insert_resource(config);
+        scene.insert_resource(watcher);
+        scene.insert_resource(Keymap::new());
+        scene.insert_resource(Time::new());
+        scene.insert_resource(Integrator::default());
+        scene.insert_resource(CollisionEvents::new());
+        scene.insert_resource(AnimationEvents::new());
+        let seed = save.data.settings.seed.unwrap_or_else(|| Rng::from_time().next_u64());
+        scene.insert_resource(PipeSpawner::new(seed));
+        scene.insert_resource(save);
+        let difficulty_path = std::path::Path::new("config/difficulty.toml");
+        scene.insert_resource(Difficulty::from_path(difficulty_path).unwrap_or_else(|e| {
+            eprintln!(
+                "{} could not load {}: {}; using the default difficulty",
+                "warning:".bold().red(),
+                difficulty_path.display(),
+                e
+            );
+            Difficulty::new()
+        }));
+        scene.insert_resource(State::new(GameState::Title));
+        scene.insert_resource(Score::new());
+
         Self {
-            scene: Scene::new(),
-            renderer: Renderer::new(),
-            cam: Camera::new(Point3::new(0.0, 3.0, 0.0), Point3::new(0.0, 0.0, 0.0)),
-            keymap: Keymap::new(),
-            player: Entity::new(),
-            pipes: Entity::new(),
-            shader: Shader::new(
-                std::path::Path::new("shaders/hello.vs"),
-                std::path::Path::new("shaders/hello.fs"),
-            ),
-            sprite: Texture::from_path(std::path::Path::new("textures/sprites.png")),
-            rot: 0.0,
+            scene,
+            schedule: Self::schedule(),
+            player,
         }
     }
+    /// The systems that make up a frame of the game, and the hooks run as it
+    /// moves between states.
+    pub fn schedule() -> Schedule {
+        use GameState::*;
+
+        let mut schedule = Schedule::new();
+        schedule
+            .add_system(Stage::Input, "start", start)
+            .in_states(&[Title, GameOver]);
+        schedule
+            .add_system(Stage::Input, "ready_tap", ready_tap)
+            .in_state(GetReady);
+        schedule.add_system(Stage::Input, "flap", flap).in_state(Playing);
+        schedule.add_system(Stage::Input, "toggle_effects", toggle_effects);
+
+        schedule
+            .add_system(Stage::FixedUpdate, "hover", hover)
+            .in_states(&[Title, GetReady]);
+        schedule
+            .add_system(Stage::FixedUpdate, "scroll_ground", scroll_ground)
+            .in_states(&[Title, GetReady, Playing]);
+        schedule
+            .add_system(Stage::FixedUpdate, "integrate", physics::integrate)
+            .in_states(&[Playing, Dying]);
+        schedule
+            .add_system(Stage::FixedUpdate, "apply_difficulty", difficulty::apply_difficulty)
+            .after("integrate")
+            .in_state(Playing);
+        schedule
+            .add_system(Stage::FixedUpdate, "spawn_pipes", spawner::spawn_pipes)
+            .after("apply_difficulty")
+            .after("integrate")
+            .in_state(Playing);
+        schedule
+            .add_system(Stage::FixedUpdate, "score_pipes", score::score_pipes)
+            .after("integrate")
+            .in_state(Playing);
+        schedule
+            .add_system(Stage::FixedUpdate, "clamp_player", clamp_player)
+            .after("integrate")
+            .in_states(&[Playing, Dying]);
+        schedule
+            .add_system(Stage::FixedUpdate, "detect_collisions", collision::detect_collisions)
+            .after("spawn_pipes")
+            .after("clamp_player")
+            .in_state(Playing);
+        schedule
+            .add_system(Stage::FixedUpdate, "check_crash", check_crash)
+            .after("detect_collisions")
+            .in_state(Playing);
+        schedule
+            .add_system(Stage::FixedUpdate, "check_landed", check_landed)
+            .after("clamp_player")
+            .in_state(Dying);
+        schedule.add_system(Stage::Update, "animate", animation::animate);
+        schedule.add_system(Stage::Update, "fade_flash", fade_flash);
+        schedule.add_system(Stage::Update, "reload_config", |scene| {
+            if config::reload_config(scene) {
+                apply_config(scene);
+            }
+        });
+        schedule
+            .add_system(Stage::LateUpdate, "update_score_hud", score::update_score_hud)
+            .in_states(&[Playing, Dying, GameOver]);
+        schedule.add_system(Stage::Render, "draw", draw);
+
+        schedule.on_enter(Title, "reset_round", reset_round);
+        schedule.on_enter(Title, "show_title", |scene| {
+            show_banner(scene, "title", 60.0);
+        });
+        schedule.on_exit(Title, "hide_banners", hide_banners);
+        schedule.on_enter(Title, "hide_score_hud", score::hide_score_hud);
+        schedule.on_enter(GetReady, "reset_round", reset_round);
+        schedule.on_enter(GetReady, "hide_score_hud", score::hide_score_hud);
+        schedule.on_enter(GetReady, "show_get_ready", |scene| {
+            show_banner(scene, "get_ready", 60.0);
+            show_banner(scene, "tap", -40.0);
+        });
+        schedule.on_exit(GetReady, "hide_banners", hide_banners);
+        schedule.on_enter(Dying, "stop_pipes", stop_pipes);
+        schedule.on_enter(Dying, "flash", flash);
+        schedule.on_enter(Dying, "fold_wings", |scene| {
+            for animation in scene.query::<&mut Animation>().with::<Player>() {
+                animation.playing = false;
+            }
+        });
+        schedule.on_enter(GameOver, "record_score", record_score);
+        schedule.on_enter(GameOver, "show_game_over", |scene| {
+            show_banner(scene, "game_over", 60.0);
+        });
+        schedule.on_exit(GameOver, "hide_banners", hide_banners);
+        schedule.on_exit(GameOver, "new_course", new_course);
+        schedule
+    }
     pub fn setup(&mut self) {
-        self.player.add_mesh(
-            &mut self.scene,
-            Mesh {
-                verts: Quad::new_square().verts.to_vec(),
-                elements: Quad::new_square().elements.to_vec(),
-                translation: Translation3::new(0.0, 0.0, 0.0),
-                rotation: Rotation3::new(Vector3::new(0.0, 1.0, 1.0) * 0.0),
-                scale: Scale3::new(0.5, 0.5, 0.5),
-            },
-        );
-        self.player.get_mesh(&mut self.scene).unwrap().verts[0].uv = nalgebra::Point2::new(0.005859, 0.011719);
-        self.player.get_mesh(&mut self.scene).unwrap().verts[1].uv = nalgebra::Point2::new(0.039062, 0.011719);
-        self.player.get_mesh(&mut self.scene).unwrap().verts[2].uv = nalgebra::Point2::new(0.039062, 0.044922);
-        self.player.get_mesh(&mut self.scene).unwrap().verts[3].uv = nalgebra::Point2::new(0.005859, 0.044922);
+        let bird = self.scene.resource::<GameConfig>().cloned().unwrap_or_default().bird;
+        self.player.add_mesh(&mut self.scene, bird_mesh());
         self.player
             .add_velocity(&mut self.scene, Vector3::new(0.0, 0.0, 2.0));
         self.player
-            .add_acceleration(&mut self.scene, Vector3::new(0.0, 0.0, -0.17));
+            .add_acceleration(&mut self.scene, Vector3::new(0.0, 0.0, bird.gravity));
         self.player
-            .add_rot_velocity(&mut self.scene, 1.0 * std::f32::consts::PI / 180.0);
+            .add_rot_velocity(&mut self.scene, Vector3::new(0.0, 1.0 * std::f32::consts::PI / 180.0, 0.0));
         self.player
-            .add_rot_acceleration(&mut self.scene, 0.1 * std::f32::consts::PI / 180.0);
-        self.pipes.add_mesh(
-            &mut self.scene,
-            Mesh {
-                verts: vec![
-                                 Vert::new(
-                                     Point3::new(0.0, -1.0, 0.0-200.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.164062, 0.056641),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(26.0, -1.0, 0.0-200.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.214844, 0.056641),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(26.0, -1.0, 160.0-200.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.214844, 0.369141),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(0.0, -1.0, 160.0-200.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.164062, 0.369141),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(0.0, -1.0, 0.0+40.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.164062, 0.369141),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(26.0, -1.0, 0.0+40.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.214844, 0.369141),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(26.0, -1.0, 160.0+40.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.214844, 0.056641),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                                 Vert::new(
-                                     Point3::new(0.0, -1.0, 160.0+40.0),
-                                     Point3::new(0.0, 0.0, 0.0),
-                                     Point2::new(0.164062, 0.056641),
-                                     Point3::new(0.0, 0.0, 0.0)),
-                ],
-                elements: vec![0, 1, 2, 0, 2, 3,
-                               4, 5, 6, 4, 6, 7],
-                translation: Translation3::new(400.0, 0.0, 0.0),
-                rotation: Rotation3::new(Vector3::new(0.0, 1.0, 0.0) * 0.0),
-                scale: Scale3::new(1.0, 1.0, 1.0),
-            },
-        );
-        self.pipes
-            .add_velocity(&mut self.scene, Vector3::new(-48.0, 0.0, 0.0));
-    }
-    pub fn handle_input(&mut self) {
-        if let Some((winit::event::ElementState::Pressed, winit::event::ElementState::Released)) =
-            self.keymap.keys.get(&winit::event::VirtualKeyCode::Space)
-        {
-            self.player
-                .get_velocity(&mut self.scene)
-                .unwrap()
-                .velocity
-                .z = 425.0;
-            *self.player
-                .get_rot_velocity(&mut self.scene)
-                .unwrap()
-                = 0.0 * std::f32::consts::PI / 180.0;
-            self.rot = -45.0 * std::f32::consts::PI / 180.0; 
-            self.keymap.keys.insert(winit::event::VirtualKeyCode::Space, (winit::event::ElementState::Released, winit::event::ElementState::Released));
+            .add_rot_acceleration(&mut self.scene, Vector3::new(0.0, bird.dive_acceleration.to_radians(), 0.0));
+        self.player.insert(&mut self.scene, Player);
+        self.player.insert(&mut self.scene, RenderLayer::new(Layer::Bird));
+        let flap = Animation::new(sprite_atlas(), ["bird_0", "bird_1", "bird_2"], 10.0, PlayMode::PingPong)
+            .unwrap_or_else(|e| panic!("could not animate the bird: {}", e));
+        self.player.insert(&mut self.scene, flap);
+        let collider = Collider::from_mesh(self.player.get_mesh(&mut self.scene).unwrap(), ShapeKind::Obb);
+        self.player.insert(&mut self.scene, collider);
+
+        spawn_bounds(&mut self.scene);
+        spawn_ground_tiles(&mut self.scene);
+    }
+    pub fn state(&self) -> GameState {
+        state::current_state(&self.scene).unwrap_or_default()
+    }
+    /// Request a transition to `next`, made at the start of the next frame.
+    pub fn set_state(&mut self, next: GameState) {
+        state::set_state(&mut self.scene, next);
+    }
+    /// Advance the game by `dt` and draw it.
+    pub fn frame(&mut self, dt: std::time::Duration) {
+        if let Some(time) = self.scene.resource_mut::<Time>() {
+            time.advance(dt);
         }
+        self.schedule.run(&mut self.scene);
     }
-    pub fn update(&mut self, _dt: std::time::Duration) {
-        self.player
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .x += self
-            .player
-            .get_acceleration(&mut self.scene)
-            .unwrap()
-            .acceleration
-            .x;
-        self.player
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .y += self
-            .player
-            .get_acceleration(&mut self.scene)
-            .unwrap()
-            .acceleration
-            .y;
-        self.player
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .z += self
-            .player
-            .get_acceleration(&mut self.scene)
-            .unwrap()
-            .acceleration
-            .z;
-        *self.player.get_rot_velocity(&mut self.scene).unwrap() += *self.player.get_rot_acceleration(&mut self.scene).unwrap();
-        self.rot += *self.player.get_rot_velocity(&mut self.scene).unwrap() * _dt.as_secs_f32();
-        self.rot = self.rot.clamp(-90.0 * std::f32::consts::PI / 180.0, 90.0 * std::f32::consts::PI / 180.0);
-        self.player.get_mesh(&mut self.scene).unwrap().rotation = Rotation3::new(Vector3::new(0.0, 1.0, 0.0) * self.rot);
-
-        self.player.get_mesh(&mut self.scene).unwrap().translation.x += self
-            .player
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .x
-            * _dt.as_secs_f32();
-        self.pipes.get_mesh(&mut self.scene).unwrap().translation.x += self
-            .pipes
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .x
-            * _dt.as_secs_f32();
-
-        if self.pipes.get_mesh(&mut self.scene).unwrap().translation.x <= -50.0 {
-            self.pipes.get_mesh(&mut self.scene).unwrap().translation.x = 50.0;
-        } else if self.pipes.get_mesh(&mut self.scene).unwrap().translation.x >= 50.0 {
-            self.pipes.get_mesh(&mut self.scene).unwrap().translation.x = -50.0;
+}
+
+/// The bird sprite, centred on the origin.
+pub fn bird_mesh() -> Mesh {
+    let mut mesh = Mesh::new(Quad::new_square().verts.to_vec(), Quad::new_square().elements.to_vec());
+    mesh.scale = Scale3::new(0.5, 0.5, 0.5);
+    for (vert, uv) in mesh.verts_mut().iter_mut().zip(sprite("bird_0").uvs()) {
+        vert.uv = uv;
+    }
+    mesh
+}
+
+/// Width of a pipe, in world units.
+pub const PIPE_WIDTH: f32 = 26.0;
+
+/// A pipe pair whose gap of height `gap` is centred on z = 0. The pipes'
+/// left edge is at x = 0.
+pub fn pipe_pair_mesh(gap: f32) -> Mesh {
+    let half_gap = gap / 2.0;
+    let bottom = sprite("pipe").uvs();
+    // the top pipe hangs upside down so its cap faces the gap
+    let top = sprite("pipe").uvs_flipped();
+    let verts = vec![
+                         Vert::new(
+                             Point3::new(0.0, 0.0, -half_gap - 160.0),
+                             Point3::new(0.0, 0.0, 0.0),
+                             bottom[0],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(PIPE_WIDTH, 0.0, -half_gap - 160.0),
+                             Point3::new(0.0, 0.0, 0.0),
+                             bottom[1],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(PIPE_WIDTH, 0.0, -half_gap),
+                             Point3::new(0.0, 0.0, 0.0),
+                             bottom[2],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(0.0, 0.0, -half_gap),
+                             Point3::new(0.0, 0.0, 0.0),
+                             bottom[3],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(0.0, 0.0, half_gap),
+                             Point3::new(0.0, 0.0, 0.0),
+                             top[0],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(PIPE_WIDTH, 0.0, half_gap),
+                             Point3::new(0.0, 0.0, 0.0),
+                             top[1],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(PIPE_WIDTH, 0.0, half_gap + 160.0),
+                             Point3::new(0.0, 0.0, 0.0),
+                             top[2],
+                             Point3::new(0.0, 0.0, 0.0)),
+                         Vert::new(
+                             Point3::new(0.0, 0.0, half_gap + 160.0),
+                             Point3::new(0.0, 0.0, 0.0),
+                             top[3],
+                             Point3::new(0.0, 0.0, 0.0)),
+    ];
+    Mesh::new(verts, vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7])
+}
+
+/// Invisible colliders along the floor and ceiling of the play area, placed
+/// by the scene's `GameConfig`.
+pub fn spawn_bounds(scene: &mut Scene) {
+    let (ground_collider, ceiling_collider) = bounds_colliders(scene);
+    let ground = scene.spawn();
+    ground.insert(scene, Ground);
+    ground.insert(scene, ground_collider);
+    let ceiling = scene.spawn();
+    ceiling.insert(scene, Ceiling);
+    ceiling.insert(scene, ceiling_collider);
+}
+
+/// Number of ground tiles, enough to cover the widest window with one to spare.
+const GROUND_TILES: usize = 8;
+
+/// A row of ground sprites along the floor, drawn instanced.
+pub fn spawn_ground_tiles(scene: &mut Scene) {
+    let floor = scene.resource::<GameConfig>().map_or(-128.0, |c| c.play_area.floor);
+    let region = sprite("ground");
+    let width = region.size().x;
+    let left = -(GROUND_TILES as f32) * width / 2.0;
+    for i in 0..GROUND_TILES {
+        let mut tile = Sprite::new(region);
+        tile.translation = Translation3::new(left + width * (i as f32 + 0.5), 0.0, floor - region.size().y / 2.0);
+        let entity = scene.spawn();
+        entity.insert(scene, tile);
+        entity.insert(scene, GroundTile);
+        entity.insert(scene, RenderLayer::new(Layer::Ground));
+    }
+}
+
+/// Move the ground along with the pipes, wrapping tiles that leave on the
+/// left round to the right.
+pub fn scroll_ground(scene: &mut Scene) {
+    let dt = scene.resource::<Time>().map_or(0.0, Time::delta_secs);
+    let speed = scene.resource::<PipeSpawner>().map_or(0.0, |s| s.speed);
+    for tile in scene.query::<&mut Sprite>().with::<GroundTile>() {
+        let width = tile.size.x;
+        let span = width * GROUND_TILES as f32;
+        tile.translation.x -= speed * dt;
+        if tile.translation.x + width / 2.0 < -span / 2.0 {
+            tile.translation.x += span;
+        }
+    }
+}
+
+/// Ground and ceiling colliders for the configured play area.
+fn bounds_colliders(scene: &Scene) -> (Collider, Collider) {
+    let area = scene.resource::<GameConfig>().cloned().unwrap_or_default().play_area;
+    let slab = |z: f32| {
+        Collider::new(Shape::Aabb {
+            center: Point2::new(0.0, z),
+            half_extents: Vector2::new(1000.0, 16.0),
+        })
+    };
+    (slab(area.floor - 16.0), slab(area.ceiling + 16.0))
+}
+
+/// Push a newly loaded `GameConfig` out to the bird, the play area bounds, the
+/// ground and the camera. Values read when used, like the flap velocity, need
+/// nothing.
+pub fn apply_config(scene: &mut Scene) {
+    let config = scene.resource::<GameConfig>().cloned().unwrap_or_default();
+    for (acceleration, rot_acceleration) in scene
+        .query::<(&mut Acceleration, &mut RotAcceleration)>()
+        .with::<Player>()
+    {
+        acceleration.acceleration.z = config.bird.gravity;
+        rot_acceleration.acceleration.y = config.bird.dive_acceleration.to_radians();
+    }
+    let (ground_collider, ceiling_collider) = bounds_colliders(scene);
+    for collider in scene.query::<&mut Collider>().with::<Ground>() {
+        *collider = ground_collider.clone();
+    }
+    for collider in scene.query::<&mut Collider>().with::<Ceiling>() {
+        *collider = ceiling_collider.clone();
+    }
+    for tile in scene.query::<&mut Sprite>().with::<GroundTile>() {
+        tile.translation.z = config.play_area.floor - tile.size.y / 2.0;
+    }
+    if let Some(camera) = scene.resource_mut::<Camera>() {
+        camera.set_zoom(config.camera.zoom);
+    }
+    if let Some(timestep) = scene.resource_mut::<FixedTimestep>() {
+        if timestep.set_tick_rate(config.timestep.tick_rate).is_ok() {
+            timestep.max_steps = config.timestep.max_steps;
+        }
+    }
+    if let Some(post) = scene.resource_mut::<PostChain>() {
+        post.effects = config.post.effects;
+    }
+}
+
+/// Whether `key` has been pressed since the last call, consuming the press.
+pub fn take_press(scene: &mut Scene, key: winit::event::VirtualKeyCode) -> bool {
+    let Some(keymap) = scene.resource_mut::<Keymap>() else {
+        return false;
+    };
+    if let Some((winit::event::ElementState::Pressed, winit::event::ElementState::Released)) = keymap.keys.get(&key) {
+        keymap.keys.insert(key, (winit::event::ElementState::Released, winit::event::ElementState::Released));
+        true
+    } else {
+        false
+    }
+}
+
+/// Kick the bird upwards.
+pub fn flap_player(scene: &mut Scene) {
+    let bird = scene.resource::<GameConfig>().cloned().unwrap_or_default().bird;
+    for (velocity, rot_velocity, mesh) in scene.query::<(&mut Velocity, &mut RotVelocity, &mut Mesh)>().with::<Player>() {
+        velocity.velocity.z = bird.flap_velocity;
+        rot_velocity.velocity.y = 0.0 * std::f32::consts::PI / 180.0;
+        mesh.rotation = Rotation3::new(Vector3::new(0.0, 1.0, 0.0) * bird.flap_pitch.to_radians());
+    }
+}
+
+/// Kick the bird upwards when space is pressed.
+pub fn flap(scene: &mut Scene) {
+    if take_press(scene, winit::event::VirtualKeyCode::Space) {
+        flap_player(scene);
+    }
+}
+
+/// Leave the title or game-over screen for the start line on a tap.
+pub fn start(scene: &mut Scene) {
+    if take_press(scene, winit::event::VirtualKeyCode::Space) {
+        state::set_state(scene, GameState::GetReady);
+    }
+}
+
+/// Start playing on a tap, with that tap as the first flap.
+pub fn ready_tap(scene: &mut Scene) {
+    if take_press(scene, winit::event::VirtualKeyCode::Space) {
+        flap_player(scene);
+        state::set_state(scene, GameState::Playing);
+    }
+}
+
+/// Bob the bird up and down while it waits at the start line.
+pub fn hover(scene: &mut Scene) {
+    let elapsed = scene.resource::<Time>().map_or(0.0, |t| t.elapsed.as_secs_f32());
+    for mesh in scene.query::<&mut Mesh>().with::<Player>() {
+        mesh.translation.z = 4.0 * (elapsed * std::f32::consts::TAU / 0.8).sin();
+    }
+}
+
+/// Start dying once the bird touches a pipe or the ground. The ceiling only
+/// stops it.
+pub fn check_crash(scene: &mut Scene) {
+    let Some(player) = scene.query::<Entity>().with::<Player>().next() else {
+        return;
+    };
+    let Some(events) = scene.resource::<CollisionEvents>() else {
+        return;
+    };
+    let crashed = events
+        .involving(player)
+        .filter_map(|contact| contact.other(player))
+        .any(|other| scene.has::<Pipe>(other) || scene.has::<Ground>(other));
+    if crashed {
+        state::set_state(scene, GameState::Dying);
+    }
+}
+
+/// End the round once the falling bird reaches the floor.
+pub fn check_landed(scene: &mut Scene) {
+    let floor = scene.resource::<GameConfig>().map_or(-128.0, |c| c.play_area.floor);
+    let landed = scene
+        .query::<&Mesh>()
+        .with::<Player>()
+        .any(|mesh| mesh.translation.z <= floor);
+    if landed {
+        state::set_state(scene, GameState::GameOver);
+    }
+}
+
+/// Seconds the death flash takes to fade.
+const FLASH_FADE: f32 = 0.4;
+
+/// Wash the screen out with the `flash` post effect, if there is one.
+pub fn flash(scene: &mut Scene) {
+    if let Some(post) = scene.resource_mut::<PostChain>() {
+        post.set_param("flash", "amount", Param::Float(1.0));
+        post.set_enabled("flash", true);
+    }
+}
+
+/// Fade the death flash back out, turning the effect off once it's gone so
+/// the pass isn't run for nothing.
+pub fn fade_flash(scene: &mut Scene) {
+    let delta = scene.resource::<Time>().map_or(0.0, Time::delta_secs);
+    let Some(effect) = scene.resource_mut::<PostChain>().and_then(|post| post.effect_mut("flash")) else {
+        return;
+    };
+    if !effect.enabled {
+        return;
+    }
+    if let Some(Param::Float(amount)) = effect.params.get_mut("amount") {
+        *amount = (*amount - delta / FLASH_FADE).max(0.0);
+        effect.enabled = *amount > 0.0;
+    }
+}
+
+/// Turn the first nine post effects on and off with F1 to F9.
+pub fn toggle_effects(scene: &mut Scene) {
+    use winit::event::VirtualKeyCode::*;
+    for (i, key) in [F1, F2, F3, F4, F5, F6, F7, F8, F9].into_iter().enumerate() {
+        if !take_press(scene, key) {
+            continue;
+        }
+        if let Some(effect) = scene.resource_mut::<PostChain>().and_then(|post| post.effects.get_mut(i)) {
+            effect.enabled = !effect.enabled;
+        }
+    }
+}
+
+/// Freeze the pipes where they are.
+pub fn stop_pipes(scene: &mut Scene) {
+    for velocity in scene.query::<&mut Velocity>().with::<Pipe>() {
+        velocity.velocity = Vector3::zeros();
+    }
+}
+
+/// Clear the course and score and put the bird back at the start line.
+pub fn reset_round(scene: &mut Scene) {
+    let pipes: Vec<Entity> = scene.query::<Entity>().with::<Pipe>().collect();
+    for pipe in pipes {
+        scene.despawn(pipe);
+    }
+    if scene.has_resource::<Difficulty>() {
+        scene.resource_scope(|scene, difficulty: &mut Difficulty| {
+            difficulty.reset();
+            if let Some(spawner) = scene.resource_mut::<PipeSpawner>() {
+                difficulty.apply(difficulty.progress(0, 0.0), spawner);
+            }
+        });
+    }
+    if let Some(spawner) = scene.resource_mut::<PipeSpawner>() {
+        spawner.reset();
+    }
+    if let Some(score) = scene.resource_mut::<Score>() {
+        score.value = 0;
+    }
+    for (mesh, velocity, rot_velocity, previous) in scene
+        .query::<(&mut Mesh, &mut Velocity, &mut RotVelocity, Option<&mut PreviousTransform>)>()
+        .with::<Player>()
+    {
+        mesh.translation = Translation3::new(0.0, 0.0, 0.0);
+        mesh.rotation = Rotation3::identity();
+        velocity.velocity = Vector3::zeros();
+        rot_velocity.velocity = Vector3::zeros();
+        // don't interpolate from where the last round ended
+        if let Some(previous) = previous {
+            *previous = PreviousTransform::of(mesh);
         }
+    }
+    for animation in scene.query::<&mut Animation>().with::<Player>() {
+        animation.restart();
+    }
+}
+
+/// Lay out a different course for the next round, unless the settings fix
+/// the seed.
+pub fn new_course(scene: &mut Scene) {
+    if scene
+        .resource::<SaveFile>()
+        .is_some_and(|save| save.data.settings.seed.is_some())
+    {
+        return;
+    }
+    if let Some(spawner) = scene.resource_mut::<PipeSpawner>() {
+        spawner.reseed(Rng::from_time().next_u64());
+    }
+}
+
+/// Put the round's score in the high-score table.
+pub fn record_score(scene: &mut Scene) {
+    let score = scene.resource::<Score>().map_or(0, |s| s.value);
+    let seed = scene.resource::<PipeSpawner>().map_or(0, PipeSpawner::seed);
+    if let Some(save) = scene.resource_mut::<SaveFile>() {
+        save.record(score, seed);
+    }
+}
+
+/// The atlas describing `textures/sprites.png`, loaded on first use.
+pub fn sprite_atlas() -> &'static Atlas {
+    static ATLAS: OnceLock<Atlas> = OnceLock::new();
+    ATLAS.get_or_init(|| {
+        let path = std::path::Path::new("textures/sprites.png");
+        Atlas::from_path(path).unwrap_or_else(|e| panic!("could not load atlas for {}: {}", path.display(), e))
+    })
+}
+
+/// The region of `sprites.png` called `name`.
+pub fn sprite(name: &str) -> &'static Region {
+    sprite_atlas()
+        .region(name)
+        .unwrap_or_else(|| panic!("no sprite `{}` in textures/sprites.toml", name))
+}
+
+/// A quad the size of `region` in pixels, centred on `center` in the x/z
+/// plane and textured with it.
+pub fn sprite_quad(region: &Region, center: Point2<f32>) -> [Vert; 4] {
+    let size = region.size();
+    let (left, right) = (center.x - size.x / 2.0, center.x + size.x / 2.0);
+    let (bottom, top) = (center.y - size.y / 2.0, center.y + size.y / 2.0);
+    let uvs = region.uvs();
+    let vert = |x: f32, z: f32, uv: Point2<f32>| {
+        Vert::new(
+            Point3::new(x, 0.0, z),
+            Point3::new(0.0, 0.0, 0.0),
+            uv,
+            Point3::new(0.0, 0.0, 0.0),
+        )
+    };
+    [
+        vert(left, bottom, uvs[0]),
+        vert(right, bottom, uvs[1]),
+        vert(right, top, uvs[2]),
+        vert(left, top, uvs[3]),
+    ]
+}
+
+/// A mesh made of a quad per sprite, each centred where given.
+pub fn sprites_mesh<'a>(sprites: impl IntoIterator<Item = (&'a Region, Point2<f32>)>) -> Mesh {
+    let mut verts = Vec::new();
+    let mut elements = Vec::new();
+    for (region, center) in sprites {
+        let offset = verts.len() as u32;
+        verts.extend(sprite_quad(region, center));
+        elements.extend([0, 1, 2, 0, 2, 3].map(|e| e + offset));
+    }
+    Mesh::new(verts, elements)
+}
+
+/// The sprite called `name`, centred on the origin.
+pub fn sprite_mesh(name: &str) -> Mesh {
+    sprites_mesh([(sprite(name), Point2::origin())])
+}
 
-        self.player.get_mesh(&mut self.scene).unwrap().translation.y += self
-            .player
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .y
-            * _dt.as_secs_f32();
-        self.player.get_mesh(&mut self.scene).unwrap().translation.z += self
-            .player
-            .get_velocity(&mut self.scene)
-            .unwrap()
-            .velocity
-            .z
-            * _dt.as_secs_f32();
-
-        
-
-        self.player.get_mesh(&mut self.scene).unwrap().translation.z = self
-            .player
-            .get_mesh(&mut self.scene)
-            .unwrap()
-            .translation
-            .z
-            .clamp(-128.0, 128.0);
-
-    }
-    pub fn draw(&mut self) {
-        self.shader.enable();
-        self.shader.set_tex("tex", &self.sprite);
-        self.shader.set_mat4("view", self.cam.view());
-        self.shader.set_mat4("cam", self.cam.perspective());
-        self.renderer.update_meshes(&self.scene.meshes);
-        self.renderer.gen_arrays();
-        self.renderer.update_buffer();
-        self.renderer.newrender(&self.scene);
+/// Show the sprite `name` in front of the play area, centred at height `z`.
+pub fn show_banner(scene: &mut Scene, name: &str, z: f32) -> Entity {
+    let mut mesh = sprite_mesh(name);
+    mesh.translation = Translation3::new(0.0, 0.0, z);
+    let banner = scene.spawn();
+    banner.add_mesh(scene, mesh);
+    banner.insert(scene, Banner);
+    banner.insert(scene, RenderLayer::new(Layer::Overlay));
+    banner
+}
+
+pub fn hide_banners(scene: &mut Scene) {
+    let banners: Vec<Entity> = scene.query::<Entity>().with::<Banner>().collect();
+    for banner in banners {
+        scene.despawn(banner);
+    }
+}
+
+/// Keep the bird on screen and stop it pitching past vertical.
+pub fn clamp_player(scene: &mut Scene) {
+    let config = scene.resource::<GameConfig>().cloned().unwrap_or_default();
+    let (floor, ceiling) = (config.play_area.floor, config.play_area.ceiling);
+    let max_pitch = config.bird.max_pitch.to_radians();
+    for mesh in scene.query::<&mut Mesh>().with::<Player>() {
+        mesh.translation.z = mesh.translation.z.clamp(floor, ceiling);
+        let pitch = mesh.rotation.scaled_axis().y;
+        mesh.rotation = Rotation3::new(Vector3::new(0.0, 1.0, 0.0) * pitch.clamp(-max_pitch, max_pitch));
     }
 }
+
+pub fn draw(scene: &mut Scene) {
+    scene.resource_scope(|scene, renderer: &mut Renderer| {
+        let alpha = scene.resource::<FixedTimestep>().map_or(1.0, FixedTimestep::alpha);
+        let sprites: Vec<_> = scene.query_ref::<(Entity, &Sprite, Option<&RenderLayer>)>().collect();
+        let meshes: Vec<_> = scene
+            .query_ref::<(Entity, &Mesh, Option<&PreviousTransform>, Option<&RenderLayer>)>()
+            .collect();
+        renderer.prepare(&meshes, &sprites, alpha);
+        match scene.remove_resource::<PostChain>() {
+            Some(mut post) => {
+                post.render(renderer, scene);
+                scene.insert_resource(post);
+            }
+            None => renderer.newrender(scene),
+        }
+    });
+}
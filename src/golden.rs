@@ -0,0 +1,143 @@
+use crate::image::{Image, ImageError};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Environment variable that makes `Golden::check` overwrite the golden images
+/// with what was rendered instead of comparing against them.
+pub const UPDATE_VAR: &str = "FLAPPY_UPDATE_GOLDEN";
+
+/// Compares rendered images against reference PNGs.
+///
+/// `check(name, image)` compares against `<dir>/<name>.png`. On a mismatch the
+/// rendered image and a diff highlighting the differing pixels are written to
+/// `<out_dir>/<name>.actual.png` and `<out_dir>/<name>.diff.png`. Run with
+/// `FLAPPY_UPDATE_GOLDEN=1` to accept new output as the reference.
+#[derive(Debug, Clone)]
+pub struct Golden {
+    pub dir: PathBuf,
+    pub out_dir: PathBuf,
+    /// Largest difference in any channel for a pixel to still match.
+    pub tolerance: u8,
+    /// How many pixels may differ before the check fails, to absorb
+    /// rasterization differences between drivers along edges.
+    pub max_mismatched: usize,
+}
+
+impl Golden {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            out_dir: PathBuf::from("target/golden"),
+            tolerance: 2,
+            max_mismatched: 0,
+        }
+    }
+    pub fn check(&self, name: &str, image: &Image) -> Result<(), GoldenError> {
+        let path = self.dir.join(format!("{}.png", name));
+        if std::env::var_os(UPDATE_VAR).is_some() {
+            std::fs::create_dir_all(&self.dir).map_err(|e| GoldenError::Image(ImageError::Io(e)))?;
+            return image.save(&path).map_err(GoldenError::Image);
+        }
+        let expected = match Image::from_path(&path) {
+            Ok(expected) => expected,
+            Err(e) => {
+                let actual = self.write_output(name, image, None)?;
+                return Err(GoldenError::Missing { path, actual, error: e });
+            }
+        };
+        if (image.width, image.height) != (expected.width, expected.height) {
+            let actual = self.write_output(name, image, None)?;
+            return Err(GoldenError::Size {
+                expected: (expected.width, expected.height),
+                actual: (image.width, image.height),
+                output: actual,
+            });
+        }
+        let comparison = image.compare(&expected, self.tolerance);
+        if comparison.mismatched <= self.max_mismatched {
+            return Ok(());
+        }
+        let actual = self.write_output(name, image, Some(&comparison.diff))?;
+        Err(GoldenError::Mismatch {
+            path,
+            mismatched: comparison.mismatched,
+            max_difference: comparison.max_difference,
+            actual,
+        })
+    }
+    /// Write the rendered image, and the diff if there is one, to `out_dir`.
+    /// Returns where the rendered image went.
+    fn write_output(&self, name: &str, image: &Image, diff: Option<&Image>) -> Result<PathBuf, GoldenError> {
+        std::fs::create_dir_all(&self.out_dir).map_err(|e| GoldenError::Image(ImageError::Io(e)))?;
+        let actual = self.out_dir.join(format!("{}.actual.png", name));
+        image.save(&actual).map_err(GoldenError::Image)?;
+        if let Some(diff) = diff {
+            diff.save(&self.out_dir.join(format!("{}.diff.png", name)))
+                .map_err(GoldenError::Image)?;
+        }
+        Ok(actual)
+    }
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    /// The golden image couldn't be read.
+    Missing {
+        path: PathBuf,
+        actual: PathBuf,
+        error: ImageError,
+    },
+    Size {
+        expected: (u32, u32),
+        actual: (u32, u32),
+        output: PathBuf,
+    },
+    Mismatch {
+        path: PathBuf,
+        mismatched: usize,
+        max_difference: u8,
+        actual: PathBuf,
+    },
+    /// Writing the outputs failed.
+    Image(ImageError),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoldenError::Missing { path, actual, error } => write!(
+                f,
+                "could not read golden {}: {}; rendered image is at {} (set {} to accept it)",
+                path.display(),
+                error,
+                actual.display(),
+                UPDATE_VAR
+            ),
+            GoldenError::Size { expected, actual, output } => write!(
+                f,
+                "rendered {}x{} but the golden is {}x{}; rendered image is at {}",
+                actual.0,
+                actual.1,
+                expected.0,
+                expected.1,
+                output.display()
+            ),
+            GoldenError::Mismatch {
+                path,
+                mismatched,
+                max_difference,
+                actual,
+            } => write!(
+                f,
+                "{} pixels differ from {} (by up to {}); rendered image and diff are in {}",
+                mismatched,
+                path.display(),
+                max_difference,
+                actual.parent().unwrap_or(actual).display()
+            ),
+            GoldenError::Image(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
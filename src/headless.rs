@@ -0,0 +1,68 @@
+use crate::image::Image;
+use crate::renderer::target::{RenderTarget, TargetDesc};
+use crate::renderer::texture::TextureFormat;
+use glutin::api::egl::context::PossiblyCurrentContext;
+use glutin::api::egl::device::Device;
+use glutin::api::egl::display::Display;
+use glutin::config::{ConfigSurfaceTypes, ConfigTemplateBuilder};
+use glutin::context::{ContextApi, ContextAttributesBuilder, GlProfile, Version};
+use glutin::display::GlDisplay;
+use std::error::Error;
+use std::ffi::CString;
+
+/// A GL context with no window, drawing into an offscreen framebuffer.
+///
+/// Made current on an EGL device display without a surface, so it works with
+/// no display server, e.g. on Mesa's llvmpipe software renderer. Everything
+/// drawn while it is current lands in its framebuffer, which `read_pixels`
+/// reads back.
+pub struct Headless {
+    pub width: u32,
+    pub height: u32,
+    /// Dropped before the context it belongs to.
+    target: RenderTarget,
+    _context: PossiblyCurrentContext,
+}
+
+impl Headless {
+    pub fn new(width: u32, height: u32) -> Result<Headless, Box<dyn Error>> {
+        let mut last_error: Box<dyn Error> = "no EGL devices".into();
+        for device in Device::query_devices()? {
+            match Self::on_device(&device, width, height) {
+                Ok(headless) => return Ok(headless),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    fn on_device(device: &Device, width: u32, height: u32) -> Result<Headless, Box<dyn Error>> {
+        let display = unsafe { Display::with_device(device, None)? };
+        let template = ConfigTemplateBuilder::default()
+            .with_surface_type(ConfigSurfaceTypes::empty())
+            .build();
+        let config = unsafe { display.find_configs(template) }?
+            .next()
+            .ok_or("no EGL config")?;
+        let attrs = ContextAttributesBuilder::new()
+            .with_profile(GlProfile::Core)
+            .with_context_api(ContextApi::OpenGl(Some(Version { major: 4, minor: 5 })))
+            .build(None);
+        let context = unsafe { display.create_context(&config, &attrs)? }.make_current_surfaceless()?;
+        gl::load_with(|s| display.get_proc_address(CString::new(s).unwrap().as_c_str()));
+
+        let target = RenderTarget::new(&TargetDesc::new(width, height).with_depth(TextureFormat::DepthStencil))?;
+        target.bind();
+        Ok(Headless {
+            width,
+            height,
+            target,
+            _context: context,
+        })
+    }
+
+    /// Read the framebuffer back, top row first.
+    pub fn read_pixels(&self) -> Image {
+        self.target.read_pixels()
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// An 8-bit RGBA image held in memory, top row first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(pixels.len(), (width * height * 4) as usize, "pixels don't fit the image size");
+        Self { width, height, pixels }
+    }
+    /// Read an 8-bit grayscale, RGB or RGBA PNG.
+    pub fn from_path(path: &Path) -> Result<Self, ImageError> {
+        let file = File::open(path).map_err(ImageError::Io)?;
+        let mut reader = png::Decoder::new(file).read_info().map_err(ImageError::Decode)?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(ImageError::Decode)?;
+        if info.bit_depth != png::BitDepth::Eight {
+            return Err(ImageError::Format(format!("{:?} bit depth", info.bit_depth)));
+        }
+        let bytes = &buf[..info.buffer_size()];
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => bytes.to_vec(),
+            png::ColorType::Rgb => bytes.chunks(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+            png::ColorType::Grayscale => bytes.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+            png::ColorType::GrayscaleAlpha => bytes.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+            other => return Err(ImageError::Format(format!("{:?} color type", other))),
+        };
+        Ok(Self::new(info.width, info.height, pixels))
+    }
+    /// Write the image as an RGBA PNG.
+    pub fn save(&self, path: &Path) -> Result<(), ImageError> {
+        let file = File::create(path).map_err(ImageError::Io)?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(ImageError::Encode)?;
+        writer.write_image_data(&self.pixels).map_err(ImageError::Encode)?;
+        writer.finish().map_err(ImageError::Encode)
+    }
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = ((y * self.width + x) * 4) as usize;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+    /// Compare against `expected` pixel by pixel. A pixel matches when no
+    /// channel differs by more than `tolerance`.
+    pub fn compare(&self, expected: &Image, tolerance: u8) -> Comparison {
+        assert_eq!(
+            (self.width, self.height),
+            (expected.width, expected.height),
+            "compared images differ in size"
+        );
+        let mut mismatched = 0;
+        let mut max_difference = 0;
+        let mut diff = Vec::with_capacity(self.pixels.len());
+        for (actual, expected) in self.pixels.chunks(4).zip(expected.pixels.chunks(4)) {
+            let difference = actual.iter().zip(expected).map(|(a, e)| a.abs_diff(*e)).max().unwrap_or(0);
+            max_difference = max_difference.max(difference);
+            if difference > tolerance {
+                mismatched += 1;
+                diff.extend_from_slice(&[255, 0, 255, 255]);
+            } else {
+                // matching pixels are dimmed so the mismatches stand out
+                let gray = ((actual[0] as u32 + actual[1] as u32 + actual[2] as u32) / 12) as u8;
+                diff.extend_from_slice(&[gray, gray, gray, 255]);
+            }
+        }
+        Comparison {
+            mismatched,
+            max_difference,
+            diff: Image::new(self.width, self.height, diff),
+        }
+    }
+}
+
+/// Result of `Image::compare`.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    /// Pixels differing by more than the tolerance.
+    pub mismatched: usize,
+    /// Largest difference in any channel of any pixel.
+    pub max_difference: u8,
+    /// The mismatched pixels in magenta over a dimmed copy of the image.
+    pub diff: Image,
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(std::io::Error),
+    Decode(png::DecodingError),
+    Encode(png::EncodingError),
+    /// A PNG this reader doesn't handle.
+    Format(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Io(e) => write!(f, "{}", e),
+            ImageError::Decode(e) => write!(f, "{}", e),
+            ImageError::Encode(e) => write!(f, "{}", e),
+            ImageError::Format(format) => write!(f, "unsupported image format: {}", format),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
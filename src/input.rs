@@ -6,6 +6,12 @@ pub struct Keymap {
     pub keys: HashMap<winit::event::VirtualKeyCode, (winit::event::ElementState, winit::event::ElementState)>
 }
 
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Keymap {
     pub fn new() -> Self {
         Self {
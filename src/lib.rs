@@ -1,8 +1,27 @@
 pub mod scene;
+pub mod world;
+pub mod query;
+pub mod schedule;
+pub mod state;
+pub mod config;
+pub mod time;
+pub mod timestep;
+pub mod physics;
+pub mod collision;
+pub mod broadphase;
+pub mod rng;
+pub mod spawner;
+pub mod animation;
+pub mod difficulty;
+pub mod score;
+pub mod save;
 pub mod entity;
 pub mod components;
 pub mod renderer;
 pub mod windowing;
+pub mod headless;
+pub mod image;
+pub mod golden;
 pub mod game;
 pub mod input;
 pub mod shader;
@@ -1,17 +1,10 @@
-use flappy;
-use glutin;
-
 use glutin::surface::GlSurface;
 
-use flappy::components::Mesh;
-use flappy::entity::Entity;
+use flappy::camera::Camera;
 use flappy::input::Keymap;
-use flappy::renderer::primatives::Cube;
 use flappy::renderer::Renderer;
-use flappy::scene::Scene;
-use flappy::shader::Shader;
-use flappy::windowing::Window;
-use nalgebra::{Rotation3, Scale3, Translation3, Vector3};
+use flappy::save::SaveFile;
+
 use winit::{
     self,
     event::{Event, WindowEvent},
@@ -21,8 +14,10 @@ fn main() {
     let window = flappy::windowing::new().expect("Could not create window");
 
     let mut flappy = flappy::game::Game::new();
-    let mut time = std::time::SystemTime::now();
-    let mut d_time = time.elapsed().unwrap();
+    if flappy.scene.resource::<SaveFile>().is_some_and(|save| save.data.settings.fullscreen) {
+        window.window.set_fullscreen(Some(winit::window::Fullscreen::Borderless(None)));
+    }
+    let mut last_frame = std::time::Instant::now();
     flappy.setup();
     window.event_loop.run(move |event, _, control_flow| {
         control_flow.set_poll();
@@ -32,18 +27,19 @@ fn main() {
                 event:
                     winit::event::DeviceEvent::Key(winit::event::KeyboardInput {
                         scancode: _,
-                        state: state,
+                        state,
                         virtual_keycode: Some(keycode),
-                        modifiers: _,
+                        ..
                     }),
             } => {
-                if let Some((current_state, _)) = flappy.keymap.keys.get(&keycode) {
-                    flappy.keymap.keys.insert(
+                let keymap = flappy.scene.resource_mut::<Keymap>().unwrap();
+                if let Some((current_state, _)) = keymap.keys.get(&keycode) {
+                    keymap.keys.insert(
                         keycode,
                         (state, *current_state),
                         );
                 } else {
-                    flappy.keymap.keys.insert(
+                    keymap.keys.insert(
                         keycode,
                         (state, state),
                         );
@@ -68,19 +64,18 @@ fn main() {
                 unsafe {
                     gl::Viewport(0, 0, size.width as i32, size.height as i32);
                 }
-                flappy.cam.resize(size.width, size.height);
+                flappy.scene.resource_mut::<Camera>().unwrap().resize(size.width, size.height);
+                flappy.scene.resource_mut::<Renderer>().unwrap().resize(size.width, size.height);
             }
             Event::MainEventsCleared => {
-                time = std::time::SystemTime::now();
+                let now = std::time::Instant::now();
+                let d_time = now - last_frame;
+                last_frame = now;
 
-                flappy.handle_input();
-                flappy.update(d_time);
-                flappy.draw();
+                flappy.frame(d_time);
 
                 let _ = window.gl_surface.swap_buffers(&window.gl_context);
                 window.window.request_redraw();
-
-                d_time = time.elapsed().unwrap();
             }
             _ => (),
         }
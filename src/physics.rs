@@ -0,0 +1,81 @@
+use crate::components::{Acceleration, Mesh, RotAcceleration, RotVelocity, Velocity};
+use crate::scene::Scene;
+use crate::time::Time;
+use nalgebra::{Rotation3, Vector3};
+
+/// Numerical scheme used to step velocities and positions forward.
+///
+/// Accelerations and velocities are per second, so every scheme gives the same
+/// trajectory regardless of frame rate (up to its own integration error).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    /// Position from the old velocity, then velocity. Cheapest, gains energy.
+    ExplicitEuler,
+    /// Velocity first, then position from the new velocity. Stable for games.
+    #[default]
+    SemiImplicitEuler,
+    /// Velocity Verlet; exact for constant acceleration.
+    Verlet,
+}
+
+impl Integrator {
+    /// Advance `position` and `velocity` by `dt` seconds under `acceleration`.
+    pub fn step(
+        &self,
+        position: &mut Vector3<f32>,
+        velocity: &mut Vector3<f32>,
+        acceleration: &Vector3<f32>,
+        dt: f32,
+    ) {
+        match self {
+            Integrator::ExplicitEuler => {
+                *position += *velocity * dt;
+                *velocity += acceleration * dt;
+            }
+            Integrator::SemiImplicitEuler => {
+                *velocity += acceleration * dt;
+                *position += *velocity * dt;
+            }
+            Integrator::Verlet => {
+                *position += *velocity * dt + acceleration * (0.5 * dt * dt);
+                *velocity += acceleration * dt;
+            }
+        }
+    }
+}
+
+/// Integrate `Velocity`/`Acceleration` into mesh translations and
+/// `RotVelocity`/`RotAcceleration` into mesh rotations for every entity, using
+/// the scene's `Integrator` resource (semi-implicit Euler if there is none).
+///
+/// Rotational velocities are scaled axes in radians per second.
+pub fn integrate(scene: &mut Scene) {
+    let dt = scene.resource::<Time>().map_or(0.0, Time::delta_secs);
+    let integrator = scene.resource::<Integrator>().copied().unwrap_or_default();
+    let zero = Vector3::zeros();
+
+    for (mesh, velocity, acceleration) in
+        scene.query::<(&mut Mesh, &mut Velocity, Option<&Acceleration>)>()
+    {
+        let acceleration = acceleration.map_or(&zero, |a| &a.acceleration);
+        integrator.step(&mut mesh.translation.vector, &mut velocity.velocity, acceleration, dt);
+    }
+    // entities without a mesh still carry their velocity forward
+    for (velocity, acceleration) in scene.query::<(&mut Velocity, &Acceleration)>().without::<Mesh>() {
+        velocity.velocity += acceleration.acceleration * dt;
+    }
+
+    for (mesh, rot_velocity, rot_acceleration) in
+        scene.query::<(&mut Mesh, &mut RotVelocity, Option<&RotAcceleration>)>()
+    {
+        let rot_acceleration = rot_acceleration.map_or(&zero, |a| &a.acceleration);
+        let mut angle = Vector3::zeros();
+        integrator.step(&mut angle, &mut rot_velocity.velocity, rot_acceleration, dt);
+        mesh.rotation = Rotation3::new(angle) * mesh.rotation;
+    }
+    for (rot_velocity, rot_acceleration) in
+        scene.query::<(&mut RotVelocity, &RotAcceleration)>().without::<Mesh>()
+    {
+        rot_velocity.velocity += rot_acceleration.acceleration * dt;
+    }
+}
@@ -0,0 +1,339 @@
+use crate::entity::Entity;
+use crate::world::{AnyStorage, ComponentStorage, World};
+use std::any::{type_name, TypeId};
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+/// Component types a query touches and whether it writes them, used to reject
+/// queries like `(&mut Mesh, &Mesh)` that would alias.
+#[derive(Debug, Default)]
+pub struct Access {
+    entries: Vec<(TypeId, &'static str, bool)>,
+}
+
+impl Access {
+    pub fn read<T: 'static>(&mut self) {
+        self.entries.push((TypeId::of::<T>(), type_name::<T>(), false));
+    }
+    pub fn write<T: 'static>(&mut self) {
+        self.entries.push((TypeId::of::<T>(), type_name::<T>(), true));
+    }
+    fn check(&self) {
+        for (i, (id, name, mutable)) in self.entries.iter().enumerate() {
+            for (other, _, other_mutable) in &self.entries[i + 1..] {
+                if id == other && (*mutable || *other_mutable) {
+                    panic!("query borrows {} mutably and more than once", name);
+                }
+            }
+        }
+    }
+}
+
+/// Something that can be pulled out of the world for each entity of a query:
+/// `Entity`, `&T`, `&mut T`, `Option<&T>`, `Option<&mut T>` or a tuple of those.
+///
+/// # Safety
+/// `access` must report every component `fetch` hands out, so that `Query::new`
+/// can rule out two live references to the same component.
+pub unsafe trait Fetch<'w> {
+    type Item;
+    type State;
+
+    fn access(access: &mut Access);
+    /// Look up the storages this fetch needs. Returns `None` if a required
+    /// component type has never been inserted, in which case nothing matches.
+    fn prepare(world: &mut World) -> Option<Self::State>;
+    /// Entities that could possibly match, or `None` if this fetch does not
+    /// narrow the search (e.g. it only has optional components).
+    fn candidates(state: &Self::State) -> Option<&'w [Entity]>;
+    /// # Safety
+    /// Must be called at most once per entity while the world is borrowed for `'w`.
+    unsafe fn fetch(state: &Self::State, entity: Entity) -> Option<Self::Item>;
+}
+
+/// A `Fetch` that never hands out `&mut`, so it can run on a shared borrow of
+/// the world: `Entity`, `&T`, `Option<&T>` or a tuple of those.
+///
+/// # Safety
+/// `fetch` must only read through the state `prepare_shared` returns.
+pub unsafe trait ReadOnlyFetch<'w>: Fetch<'w> {
+    fn prepare_shared(world: &World) -> Option<Self::State>;
+}
+
+/// Raw handle to a component storage, valid for as long as the query's borrow
+/// of the world.
+pub struct StoragePtr<T> {
+    storage: *mut ComponentStorage<T>,
+}
+
+impl<T: 'static> StoragePtr<T> {
+    fn new(world: &mut World) -> Option<Self> {
+        world.storage_ptr::<T>().map(|storage| Self { storage })
+    }
+    /// A handle only read through, from a shared borrow.
+    fn new_shared(world: &World) -> Option<Self> {
+        let storage = world.storage::<T>()?;
+        Some(Self {
+            storage: storage as *const ComponentStorage<T> as *mut ComponentStorage<T>,
+        })
+    }
+    fn entities<'w>(&self) -> &'w [Entity] {
+        unsafe { (*self.storage).entities() }
+    }
+    unsafe fn get<'w>(&self, entity: Entity) -> Option<&'w T> {
+        let idx = (*self.storage).index_of(entity)?;
+        (*self.storage).values().get(idx)
+    }
+    unsafe fn get_mut<'w>(&self, entity: Entity) -> Option<&'w mut T> {
+        let idx = (*self.storage).index_of(entity)?;
+        Some(&mut *(*self.storage).as_mut_ptr().add(idx))
+    }
+}
+
+unsafe impl<'w> Fetch<'w> for Entity {
+    type Item = Entity;
+    type State = ();
+
+    fn access(_access: &mut Access) {}
+    fn prepare(_world: &mut World) -> Option<Self::State> {
+        Some(())
+    }
+    fn candidates(_state: &Self::State) -> Option<&'w [Entity]> {
+        None
+    }
+    unsafe fn fetch(_state: &Self::State, entity: Entity) -> Option<Self::Item> {
+        Some(entity)
+    }
+}
+
+unsafe impl<'w, T: 'static> Fetch<'w> for &'w T {
+    type Item = &'w T;
+    type State = StoragePtr<T>;
+
+    fn access(access: &mut Access) {
+        access.read::<T>();
+    }
+    fn prepare(world: &mut World) -> Option<Self::State> {
+        StoragePtr::new(world)
+    }
+    fn candidates(state: &Self::State) -> Option<&'w [Entity]> {
+        Some(state.entities())
+    }
+    unsafe fn fetch(state: &Self::State, entity: Entity) -> Option<Self::Item> {
+        state.get(entity)
+    }
+}
+
+unsafe impl<'w> ReadOnlyFetch<'w> for Entity {
+    fn prepare_shared(_world: &World) -> Option<Self::State> {
+        Some(())
+    }
+}
+
+unsafe impl<'w, T: 'static> ReadOnlyFetch<'w> for &'w T {
+    fn prepare_shared(world: &World) -> Option<Self::State> {
+        StoragePtr::new_shared(world)
+    }
+}
+
+unsafe impl<'w, T: 'static> Fetch<'w> for &'w mut T {
+    type Item = &'w mut T;
+    type State = StoragePtr<T>;
+
+    fn access(access: &mut Access) {
+        access.write::<T>();
+    }
+    fn prepare(world: &mut World) -> Option<Self::State> {
+        StoragePtr::new(world)
+    }
+    fn candidates(state: &Self::State) -> Option<&'w [Entity]> {
+        Some(state.entities())
+    }
+    unsafe fn fetch(state: &Self::State, entity: Entity) -> Option<Self::Item> {
+        state.get_mut(entity)
+    }
+}
+
+unsafe impl<'w, T: 'static> Fetch<'w> for Option<&'w T> {
+    type Item = Option<&'w T>;
+    type State = Option<StoragePtr<T>>;
+
+    fn access(access: &mut Access) {
+        access.read::<T>();
+    }
+    fn prepare(world: &mut World) -> Option<Self::State> {
+        Some(StoragePtr::new(world))
+    }
+    fn candidates(_state: &Self::State) -> Option<&'w [Entity]> {
+        None
+    }
+    unsafe fn fetch(state: &Self::State, entity: Entity) -> Option<Self::Item> {
+        Some(state.as_ref().and_then(|s| s.get(entity)))
+    }
+}
+
+unsafe impl<'w, T: 'static> ReadOnlyFetch<'w> for Option<&'w T> {
+    fn prepare_shared(world: &World) -> Option<Self::State> {
+        Some(StoragePtr::new_shared(world))
+    }
+}
+
+unsafe impl<'w, T: 'static> Fetch<'w> for Option<&'w mut T> {
+    type Item = Option<&'w mut T>;
+    type State = Option<StoragePtr<T>>;
+
+    fn access(access: &mut Access) {
+        access.write::<T>();
+    }
+    fn prepare(world: &mut World) -> Option<Self::State> {
+        Some(StoragePtr::new(world))
+    }
+    fn candidates(_state: &Self::State) -> Option<&'w [Entity]> {
+        None
+    }
+    unsafe fn fetch(state: &Self::State, entity: Entity) -> Option<Self::Item> {
+        Some(state.as_ref().and_then(|s| s.get_mut(entity)))
+    }
+}
+
+macro_rules! impl_fetch_tuple {
+    ($($name:ident),+) => {
+        unsafe impl<'w, $($name: Fetch<'w>),+> Fetch<'w> for ($($name,)+) {
+            type Item = ($($name::Item,)+);
+            type State = ($($name::State,)+);
+
+            fn access(access: &mut Access) {
+                $($name::access(access);)+
+            }
+            fn prepare(world: &mut World) -> Option<Self::State> {
+                Some(($($name::prepare(world)?,)+))
+            }
+            #[allow(non_snake_case)]
+            fn candidates(state: &Self::State) -> Option<&'w [Entity]> {
+                let ($($name,)+) = state;
+                [$($name::candidates($name)),+]
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|c| c.len())
+            }
+            #[allow(non_snake_case)]
+            unsafe fn fetch(state: &Self::State, entity: Entity) -> Option<Self::Item> {
+                let ($($name,)+) = state;
+                Some(($($name::fetch($name, entity)?,)+))
+            }
+        }
+
+        unsafe impl<'w, $($name: ReadOnlyFetch<'w>),+> ReadOnlyFetch<'w> for ($($name,)+) {
+            fn prepare_shared(world: &World) -> Option<Self::State> {
+                Some(($($name::prepare_shared(world)?,)+))
+            }
+        }
+    };
+}
+
+impl_fetch_tuple!(A);
+impl_fetch_tuple!(A, B);
+impl_fetch_tuple!(A, B, C);
+impl_fetch_tuple!(A, B, C, D);
+impl_fetch_tuple!(A, B, C, D, E);
+impl_fetch_tuple!(A, B, C, D, E, F);
+impl_fetch_tuple!(A, B, C, D, E, F, G);
+impl_fetch_tuple!(A, B, C, D, E, F, G, H);
+
+enum Filter {
+    With(Option<*const dyn AnyStorage>),
+    Without(Option<*const dyn AnyStorage>),
+}
+
+/// Iterator over every entity that has all the components `Q` asks for.
+///
+/// ```ignore
+/// for (mesh, velocity) in scene.query::<(&mut Mesh, &Velocity)>().without::<Player>() {
+///     mesh.translation.vector += velocity.velocity * dt;
+/// }
+/// ```
+pub struct Query<'w, Q: Fetch<'w>> {
+    world: *const World,
+    state: Option<Q::State>,
+    candidates: Cow<'w, [Entity]>,
+    filters: Vec<Filter>,
+    next: usize,
+    _world: PhantomData<&'w mut World>,
+}
+
+impl<'w, Q: Fetch<'w>> Query<'w, Q> {
+    pub fn new(world: &'w mut World) -> Self {
+        let state = Q::prepare(world);
+        Self::with_state(world, state)
+    }
+    fn with_state(world: &'w World, state: Option<Q::State>) -> Self {
+        let mut access = Access::default();
+        Q::access(&mut access);
+        access.check();
+
+        let candidates = match state.as_ref().map(Q::candidates) {
+            Some(Some(entities)) => Cow::Borrowed(entities),
+            Some(None) => Cow::Owned(world.entities().collect()),
+            None => Cow::Owned(Vec::new()),
+        };
+        Self {
+            world,
+            state,
+            candidates,
+            filters: Vec::new(),
+            next: 0,
+            _world: PhantomData,
+        }
+    }
+    /// Only visit `entity`, if it matches.
+    pub(crate) fn single(mut self, entity: Entity) -> Self {
+        self.candidates = Cow::Owned(vec![entity]);
+        self
+    }
+    /// Only match entities that also have a `T`.
+    pub fn with<T: 'static>(mut self) -> Self {
+        let storage = unsafe { (*self.world).storage_dyn::<T>() };
+        self.filters.push(Filter::With(storage));
+        self
+    }
+    /// Skip entities that have a `T`.
+    pub fn without<T: 'static>(mut self) -> Self {
+        let storage = unsafe { (*self.world).storage_dyn::<T>() };
+        self.filters.push(Filter::Without(storage));
+        self
+    }
+    fn passes(&self, entity: Entity) -> bool {
+        self.filters.iter().all(|filter| match filter {
+            Filter::With(Some(s)) => unsafe { (**s).contains(entity) },
+            Filter::With(None) => false,
+            Filter::Without(Some(s)) => unsafe { !(**s).contains(entity) },
+            Filter::Without(None) => true,
+        })
+    }
+}
+
+impl<'w, Q: ReadOnlyFetch<'w>> Query<'w, Q> {
+    /// A query that only reads, on a shared borrow of the world.
+    pub fn new_shared(world: &'w World) -> Self {
+        Self::with_state(world, Q::prepare_shared(world))
+    }
+}
+
+impl<'w, Q: Fetch<'w>> Iterator for Query<'w, Q> {
+    type Item = Q::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.state.as_ref()?;
+        while let Some(&entity) = self.candidates.get(self.next) {
+            self.next += 1;
+            if !self.passes(entity) {
+                continue;
+            }
+            // each candidate is visited once, so no two items alias
+            if let Some(item) = unsafe { Q::fetch(state, entity) } {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
@@ -1,236 +1,385 @@
+pub mod backend;
+pub mod layer;
+pub mod opengl;
+pub mod post;
 pub mod primatives;
+pub mod recording;
+pub mod software;
+pub mod sprites;
+pub mod target;
 pub mod texture;
 use crate::scene::Scene;
 
-use gl::{self, types::*};
-
 use crate::camera::Camera;
 use crate::components;
-use crate::renderer::texture::Texture;
-use crate::shader::Shader;
-use nalgebra::{self, Matrix4, Point3, Vector3};
+use crate::entity::Entity;
+use crate::timestep::PreviousTransform;
+use backend::{
+    Backend, BufferData, BufferId, BufferKind, DrawCall, DrawState, Frame, Geometry, Pass, PipelineId, TargetId,
+    TextureId,
+};
+use layer::{Item, RenderLayer};
+use nalgebra::{self, Matrix4, Point3, Translation3};
+use opengl::GlBackend;
+use std::collections::{HashMap, HashSet};
 use primatives::Vert;
+use sprites::SpriteInstance;
+
+/// Colour the screen is cleared to before each frame.
+pub const CLEAR_COLOR: [f32; 4] = [0.2, 0.3, 0.3, 1.0];
+
+/// Where mesh vertices are moved into world space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransformMode {
+    /// Transform every vertex on the CPU and draw all meshes as one batch.
+    #[default]
+    Cpu,
+    /// Keep each mesh's vertices on the GPU in model space, uploading them
+    /// only when they change, and draw each mesh with its own `model` matrix.
+    Gpu,
+}
+
+/// A mesh for `Renderer::prepare`: its entity, where it was at the previous
+/// fixed tick, and its place in the draw order.
+pub type MeshDraw<'a> = (
+    Entity,
+    &'a components::Mesh,
+    Option<&'a PreviousTransform>,
+    Option<&'a RenderLayer>,
+);
+
+/// A sprite for `Renderer::prepare`.
+pub type SpriteDraw<'a> = (Entity, &'a components::Sprite, Option<&'a RenderLayer>);
+
+/// One draw call of a render pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Draw {
+    /// Elements `first..first + count` of the CPU-transformed batch.
+    Batch { first: usize, count: usize },
+    /// A mesh kept on the GPU by `TransformMode::Gpu`.
+    Mesh(Entity),
+    /// Instances `first..first + count` of the sprite batch.
+    Sprites { first: usize, count: usize },
+}
 
+/// Add `draw` to a pass, folding it into the previous call when both cover
+/// neighbouring ranges of the same buffer.
+fn push_draw(pass: &mut Vec<Draw>, draw: Draw) {
+    let merged = match (pass.last(), draw) {
+        (Some(Draw::Batch { first, count }), Draw::Batch { first: next, count: n }) if first + count == next => {
+            Some(Draw::Batch { first: *first, count: count + n })
+        }
+        (Some(Draw::Sprites { first, count }), Draw::Sprites { first: next, count: n })
+            if first + count == next =>
+        {
+            Some(Draw::Sprites { first: *first, count: count + n })
+        }
+        _ => None,
+    };
+    match merged {
+        Some(merged) => *pass.last_mut().unwrap() = merged,
+        None => pass.push(draw),
+    }
+}
+
+/// A mesh's model-space geometry held in backend buffers.
+struct GpuMesh {
+    vertices: BufferId,
+    elements: BufferId,
+    /// `Mesh::generation` of what was last uploaded, to tell when the mesh
+    /// has changed.
+    generation: Option<u64>,
+    count: usize,
+    model: Matrix4<f32>,
+}
+
+impl GpuMesh {
+    fn new(backend: &mut dyn Backend) -> Self {
+        Self {
+            vertices: backend.create_buffer(BufferKind::Vertex),
+            elements: backend.create_buffer(BufferKind::Element),
+            generation: None,
+            count: 0,
+            model: Matrix4::identity(),
+        }
+    }
+    fn upload(&mut self, backend: &mut dyn Backend, mesh: &components::Mesh) {
+        if self.generation == Some(mesh.generation()) {
+            return;
+        }
+        backend.update_buffer(self.vertices, BufferData::Vertices(&mesh.verts_local()));
+        backend.update_buffer(self.elements, BufferData::Elements(mesh.elements()));
+        self.generation = Some(mesh.generation());
+        self.count = mesh.elements().len();
+    }
+    fn destroy(self, backend: &mut dyn Backend) {
+        backend.destroy_buffer(self.vertices);
+        backend.destroy_buffer(self.elements);
+    }
+}
+
+/// Sorts meshes and sprites into render passes and hands them to a `Backend`
+/// to draw.
 pub struct Renderer {
-    pub vao: GLuint,
-    pub vbo: GLuint,
-    pub ebo: GLuint,
+    backend: Box<dyn Backend>,
     pub verts: Vec<Vert>,
     pub elements: Vec<u32>,
-    pub shader: Option<Shader>,
+    /// Used by `newrender` when the scene has no `Camera`.
     pub camera: Camera,
-    pub tex: Texture,
+    /// Where `newrender` draws; the bound framebuffer, normally the screen,
+    /// when `None`.
+    pub target: Option<TargetId>,
+    /// Texture meshes and sprites sample; nothing is drawn without one.
+    pub texture: Option<TextureId>,
+    pub transform_mode: TransformMode,
+    /// Pipeline for meshes; they aren't drawn without one.
+    pub mesh_pipeline: Option<PipelineId>,
+    /// Pipeline for the instanced sprite path; sprites aren't drawn without
+    /// one.
+    pub sprite_pipeline: Option<PipelineId>,
+    /// Vertex and element buffers of the CPU-transformed batch.
+    batch: Option<(BufferId, BufferId)>,
+    instances: Option<BufferId>,
+    gpu_meshes: HashMap<Entity, GpuMesh>,
+    /// Draws of the opaque pass, front to back.
+    opaque: Vec<Draw>,
+    /// Draws of the blended pass, back to front.
+    transparent: Vec<Draw>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Renderer {
+    /// A renderer drawing with OpenGL on the current context.
     pub fn new() -> Renderer {
+        Self::with_backend(Box::new(GlBackend::new()))
+    }
+    pub fn with_backend(backend: Box<dyn Backend>) -> Renderer {
         Renderer {
-            vao: 0,
-            vbo: 0,
-            ebo: 0,
+            backend,
             verts: Vec::new(),
             elements: Vec::new(),
-            shader: None,
             camera: Camera::new(Point3::new(0.0, 2.0, 1.0), Point3::new(0.0, 0.0, 0.0)),
-            tex: Texture::from_path(std::path::Path::new("textures/container.png")),
+            target: None,
+            texture: None,
+            transform_mode: TransformMode::default(),
+            mesh_pipeline: None,
+            sprite_pipeline: None,
+            batch: None,
+            instances: None,
+            gpu_meshes: HashMap::new(),
+            opaque: Vec::new(),
+            transparent: Vec::new(),
         }
     }
-    pub fn shader_from_paths(&mut self, vs_path: &std::path::Path, fs_path: &std::path::Path) {
-        self.shader = Some(Shader::new(vs_path, fs_path));
-    }
-    pub fn set_shader(&mut self, shader: Shader) {
-        self.shader = Some(shader);
-    }
-
-    pub fn update_meshes(&mut self, meshes: &Vec<components::Mesh>) {
-        self.verts = meshes
-            .iter()
-            .map(|m| m.verts_transformed())
-            .flatten()
-            .collect();
-        let mut offset: u32 = 0;
-        self.elements = meshes
-            .iter()
-            .map(|m| {
-                let x: Vec<u32> = m
-                    .elements
-                    .clone()
-                    .into_iter()
-                    .map(|mut e| {
-                        e += offset;
-                        e
-                    })
-                    .collect();
-                offset += m.verts.len() as u32;
-                x
-            })
-            .flatten()
-            .collect();
-    }
-
-    pub fn update_buffer(&self) {
-        let vertices = self
-            .verts
-            .iter()
-            .map(|v| {
-                [
-                    v.pos.x, v.pos.y, v.pos.z, v.color.x, v.color.y, v.color.z, v.uv.x, v.uv.y,
-                    v.normal.x, v.normal.y, v.normal.z,
-                ]
-            })
-            .flatten()
-            .collect::<Vec<f32>>();
-        unsafe {
-            gl::BindVertexArray(self.vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BufferSubData(
-                gl::ARRAY_BUFFER,
-                0,
-                (vertices.len() * std::mem::size_of::<f32>()) as isize,
-                vertices.as_ptr() as *const std::ffi::c_void,
-            );
+    /// The backend, if it is a `B`.
+    pub fn backend<B: Backend>(&self) -> Option<&B> {
+        self.backend.as_any().downcast_ref()
+    }
+    /// The backend, for creating textures and pipelines.
+    pub fn backend_mut(&mut self) -> &mut dyn Backend {
+        self.backend.as_mut()
+    }
+    /// Match the renderer's target, if it has one, to a new window size.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if let Some(target) = self.target {
+            self.backend.resize_target(target, width, height);
         }
     }
 
-    pub fn gen_arrays(&mut self) {
-        let vertices = self
-            .verts
-            .iter()
-            .map(|v| {
-                [
-                    v.pos.x, v.pos.y, v.pos.z, v.color.x, v.color.y, v.color.z, v.uv.x, v.uv.y,
-                    v.normal.x, v.normal.y, v.normal.z,
-                ]
-            })
-            .flatten()
-            .collect::<Vec<f32>>();
-        static mut ONCE: bool = true;
-        unsafe {
-            if ONCE {
-                println!(
-                    "{:?}",
-                    self.verts
-                        .iter()
-                        .map(|v| [v.pos.x, v.pos.y, v.pos.z])
-                        .flatten()
-                        .collect::<Vec<f32>>()
-                );
-                println!("{:?}", self.elements);
-                println!("{:?}", self.elements.len());
-                ONCE = false;
+    /// Sort this frame's meshes and sprites by `RenderLayer` and split them
+    /// into the opaque and blended passes `newrender` draws. Each is moved
+    /// along y by its place in the order, so the depth test agrees with it.
+    /// Meshes are placed `alpha` of the way from their previous transform.
+    ///
+    /// In `TransformMode::Gpu` a mesh's vertices are uploaded only when its
+    /// `Mesh::generation` has changed, and the buffers of meshes not passed
+    /// in are destroyed.
+    pub fn prepare(&mut self, meshes: &[MeshDraw], sprites: &[SpriteDraw], alpha: f32) {
+        let items = layer::draw_order(meshes, sprites);
+
+        self.verts.clear();
+        self.elements.clear();
+        self.opaque.clear();
+        self.transparent.clear();
+        let mut opaque_sprites = Vec::new();
+        let mut transparent_sprites = Vec::new();
+        let mut drawn = HashSet::new();
+        for (rank, (item, transparent)) in items.iter().enumerate() {
+            let lift = Translation3::new(0.0, layer::depth(rank, items.len()), 0.0);
+            let draw = match *item {
+                Item::Mesh(i) => {
+                    let (entity, mesh, previous, _) = meshes[i];
+                    let (translation, rotation, scale) = placement(mesh, previous, alpha);
+                    match self.transform_mode {
+                        TransformMode::Cpu => {
+                            let first = self.elements.len();
+                            let verts = mesh.verts_transformed_by(&(lift * translation), &rotation, &scale);
+                            self.append(mesh, verts);
+                            Draw::Batch { first, count: mesh.elements().len() }
+                        }
+                        TransformMode::Gpu => {
+                            let backend = self.backend.as_mut();
+                            let gpu = self.gpu_meshes.entry(entity).or_insert_with(|| GpuMesh::new(backend));
+                            gpu.upload(backend, mesh);
+                            gpu.model =
+                                lift.to_homogeneous() * components::model_matrix(&translation, &rotation, &scale);
+                            drawn.insert(entity);
+                            Draw::Mesh(entity)
+                        }
+                    }
+                }
+                Item::Sprite(i) => {
+                    let mut instance = SpriteInstance::of(sprites[i].1);
+                    instance.model = lift.to_homogeneous() * instance.model;
+                    if !transparent {
+                        opaque_sprites.push(instance);
+                        continue;
+                    }
+                    transparent_sprites.push(instance);
+                    Draw::Sprites { first: transparent_sprites.len() - 1, count: 1 }
+                }
+            };
+            if *transparent {
+                push_draw(&mut self.transparent, draw);
+            } else {
+                self.opaque.push(draw);
             }
         }
+        let gone: Vec<Entity> = self.gpu_meshes.keys().filter(|e| !drawn.contains(e)).copied().collect();
+        for entity in gone {
+            let gpu = self.gpu_meshes.remove(&entity).unwrap();
+            gpu.destroy(self.backend.as_mut());
+        }
 
-        unsafe {
-            gl::GenVertexArrays(1, &mut self.vao);
-            gl::GenBuffers(1, &mut self.vbo);
-            gl::GenBuffers(1, &mut self.ebo);
-
-            gl::BindVertexArray(self.vao);
-
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (vertices.len() * std::mem::size_of::<f32>()) as isize,
-                vertices.as_ptr() as *const std::ffi::c_void,
-                gl::DYNAMIC_DRAW,
-            );
-
-            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (self.elements.len() * std::mem::size_of::<u32>()) as isize,
-                self.elements.as_ptr() as *const std::ffi::c_void,
-                gl::DYNAMIC_DRAW,
-            );
-
-            // enable position
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                (11 * std::mem::size_of::<f32>()) as i32,
-                (0 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
-            gl::EnableVertexAttribArray(0);
-
-            // enable color
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                (11 * std::mem::size_of::<f32>()) as i32,
-                (3 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
-            gl::EnableVertexAttribArray(1);
-
-            // enable uv
-            gl::VertexAttribPointer(
-                2,
-                2,
-                gl::FLOAT,
-                gl::FALSE,
-                (11 * std::mem::size_of::<f32>()) as i32,
-                (6 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
-            gl::EnableVertexAttribArray(2);
-
-            // enable surface normal
-            gl::VertexAttribPointer(
-                3,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                (11 * std::mem::size_of::<f32>()) as i32,
-                (8 * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
-            );
-            gl::EnableVertexAttribArray(3);
-
-            // note that this is allowed, the call to glVertexAttribPointer registered VBO as the vertex attribute's bound vertex buffer object so afterwards we can safely unbind
-            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
-
-            // You can unbind the VAO afterwards so other VAO calls won't accidentally modify this VAO, but this rarely happens. Modifying other
-            // VAOs requires a call to glBindVertexArray anyways so we generally don't unbind VAOs (nor VBOs) when it's not directly necessary.
-            gl::BindVertexArray(0);
+        // opaque meshes front to back so hidden fragments fail the depth test
+        let opaque: Vec<Draw> = self.opaque.drain(..).rev().collect();
+        for draw in opaque {
+            push_draw(&mut self.opaque, draw);
+        }
+        if !opaque_sprites.is_empty() {
+            self.opaque.push(Draw::Sprites { first: 0, count: opaque_sprites.len() });
         }
+        // blended sprites are stored after the opaque ones
+        for draw in &mut self.transparent {
+            if let Draw::Sprites { first, .. } = draw {
+                *first += opaque_sprites.len();
+            }
+        }
+        opaque_sprites.append(&mut transparent_sprites);
+
+        if self.transform_mode == TransformMode::Cpu {
+            self.upload_batch();
+        }
+        let backend = self.backend.as_mut();
+        let instances = *self.instances.get_or_insert_with(|| backend.create_buffer(BufferKind::Instance));
+        backend.update_buffer(instances, BufferData::Instances(&opaque_sprites));
+    }
+
+    /// Add a transformed mesh to the end of the CPU batch.
+    fn append(&mut self, mesh: &components::Mesh, verts: Vec<Vert>) {
+        let offset = self.verts.len() as u32;
+        self.elements.extend(mesh.elements().iter().map(|e| e + offset));
+        self.verts.extend(verts);
     }
-    pub fn render(&self) {
-        unsafe {
-            gl::BindVertexArray(self.vao); // seeing as we only have a single VAO there's no need to bind it every time, but we'll do so to keep things a bit more organized
-            self.update_buffer();
-            // draw our first triangle
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-            gl::Enable( gl::BLEND );
-            gl::Enable(gl::DEPTH_TEST);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-            //gl::DrawArrays(gl::TRIANGLES, 0, num_verts as i32);
-            //gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.elements.len() as i32,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
+
+    /// Upload the CPU batch, creating its buffers on first use.
+    fn upload_batch(&mut self) {
+        let backend = self.backend.as_mut();
+        let (vertices, elements) = *self.batch.get_or_insert_with(|| {
+            (backend.create_buffer(BufferKind::Vertex), backend.create_buffer(BufferKind::Element))
+        });
+        backend.update_buffer(vertices, BufferData::Vertices(&self.verts));
+        backend.update_buffer(elements, BufferData::Elements(&self.elements));
+    }
+
+    /// The frame `newrender` submits: the opaque pass with blending off, then
+    /// the transparent pass blended over it without writing depth. Draws are
+    /// left out while the pipeline or texture they need is missing.
+    pub fn frame(&self, camera: &Camera) -> Frame {
+        Frame {
+            target: self.target,
+            clear_color: CLEAR_COLOR,
+            view: *camera.view(),
+            projection: *camera.perspective(),
+            passes: vec![
+                Pass {
+                    state: DrawState::OPAQUE,
+                    draws: self.draw_calls(&self.opaque),
+                },
+                Pass {
+                    state: DrawState::TRANSPARENT,
+                    draws: self.draw_calls(&self.transparent),
+                },
+            ],
         }
     }
+
+    /// Clear the screen and draw what was last passed to `prepare`, seen by
+    /// the scene's `Camera`.
     pub fn newrender(&mut self, scene: &Scene) {
-        unsafe {
-            gl::BindVertexArray(self.vao); // seeing as we only have a single VAO there's no need to bind it every time, but we'll do so to keep things a bit more organized
-            gl::Enable(gl::BLEND);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Enable(gl::DEPTH_TEST);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-            gl::DrawElements(
-                gl::TRIANGLES,
-                self.elements.len() as i32,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
-        }
+        let camera = scene.resource::<Camera>().unwrap_or(&self.camera);
+        let frame = self.frame(camera);
+        self.backend.submit(&frame);
+    }
+
+    fn draw_calls(&self, pass: &[Draw]) -> Vec<DrawCall> {
+        let Some(texture) = self.texture else {
+            return Vec::new();
+        };
+        pass.iter()
+            .filter_map(|draw| {
+                let (pipeline, model, geometry) = match *draw {
+                    Draw::Batch { first, count } => {
+                        let (vertices, elements) = self.batch?;
+                        let geometry = Geometry::Indexed { vertices, elements, first, count };
+                        (self.mesh_pipeline?, Matrix4::identity(), geometry)
+                    }
+                    Draw::Mesh(entity) => {
+                        let gpu = &self.gpu_meshes[&entity];
+                        let geometry = Geometry::Indexed {
+                            vertices: gpu.vertices,
+                            elements: gpu.elements,
+                            first: 0,
+                            count: gpu.count,
+                        };
+                        (self.mesh_pipeline?, gpu.model, geometry)
+                    }
+                    Draw::Sprites { first, count } => {
+                        let geometry = Geometry::Instanced { instances: self.instances?, first, count };
+                        (self.sprite_pipeline?, Matrix4::identity(), geometry)
+                    }
+                };
+                Some(DrawCall {
+                    pipeline,
+                    texture,
+                    model,
+                    geometry,
+                    uniforms: Vec::new(),
+                })
+            })
+            .filter(|draw| match draw.geometry {
+                Geometry::Indexed { count, .. } | Geometry::Instanced { count, .. } => count > 0,
+                Geometry::FullScreen => true,
+            })
+            .collect()
+    }
+}
+
+/// Where a mesh is drawn: `alpha` of the way from its transform at the
+/// previous fixed tick to its current one.
+fn placement(
+    mesh: &components::Mesh,
+    previous: Option<&PreviousTransform>,
+    alpha: f32,
+) -> (Translation3<f32>, nalgebra::Rotation3<f32>, nalgebra::Scale3<f32>) {
+    match previous {
+        Some(previous) => previous.lerp(mesh, alpha),
+        None => (mesh.translation, mesh.rotation, mesh.scale),
     }
 }
+
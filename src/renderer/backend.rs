@@ -0,0 +1,199 @@
+use super::primatives::Vert;
+use super::sprites::SpriteInstance;
+use super::target::{TargetDesc, TargetError};
+use crate::image::Image;
+use nalgebra::Matrix4;
+use std::any::Any;
+use std::path::PathBuf;
+
+/// A graphics API the `Renderer` draws through. Resources are created and
+/// filled up front, then each frame is handed over whole as a `Frame` of draw
+/// lists, so the renderer never talks to the API directly.
+pub trait Backend: Any {
+    fn create_buffer(&mut self, kind: BufferKind) -> BufferId;
+    /// Replace a buffer's contents. The data must match the buffer's kind.
+    fn update_buffer(&mut self, buffer: BufferId, data: BufferData);
+    fn destroy_buffer(&mut self, buffer: BufferId);
+    fn create_texture(&mut self, image: &Image) -> TextureId;
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> PipelineId;
+    fn create_target(&mut self, desc: &TargetDesc) -> Result<TargetId, TargetError>;
+    /// Reallocate a target at a new size, losing what it held.
+    fn resize_target(&mut self, target: TargetId, width: u32, height: u32);
+    fn destroy_target(&mut self, target: TargetId);
+    /// The texture holding what was drawn into `target`, for sampling in
+    /// later frames.
+    fn target_texture(&self, target: TargetId) -> TextureId;
+    /// Read back the colour of `target`, top row first.
+    fn read_target(&mut self, target: TargetId) -> Image;
+    /// Clear the target and draw the frame's passes in order.
+    fn submit(&mut self, frame: &Frame);
+    fn as_any(&self) -> &dyn Any;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BufferId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PipelineId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TargetId(pub u32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferKind {
+    Vertex,
+    Element,
+    /// Per-instance sprite data, drawn over a unit quad.
+    Instance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BufferData<'a> {
+    Vertices(&'a [Vert]),
+    Elements(&'a [u32]),
+    Instances(&'a [SpriteInstance]),
+}
+
+impl BufferData<'_> {
+    pub fn kind(&self) -> BufferKind {
+        match self {
+            BufferData::Vertices(_) => BufferKind::Vertex,
+            BufferData::Elements(_) => BufferKind::Element,
+            BufferData::Instances(_) => BufferKind::Instance,
+        }
+    }
+    pub fn len(&self) -> usize {
+        match self {
+            BufferData::Vertices(data) => data.len(),
+            BufferData::Elements(data) => data.len(),
+            BufferData::Instances(data) => data.len(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Which inputs a pipeline's vertex shader reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexLayout {
+    /// `Vert`s placed by the draw's `model` matrix, as `shaders/hello.vs`.
+    Mesh,
+    /// `SpriteInstance`s over a unit quad, as `shaders/sprite.vs`.
+    Sprite,
+    /// No inputs: one triangle covering the target, as
+    /// `shaders/post/fullscreen.vs`.
+    FullScreen,
+}
+
+/// A shader program and the vertex layout it expects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineDesc {
+    pub vertex_shader: PathBuf,
+    pub fragment_shader: PathBuf,
+    pub layout: VertexLayout,
+}
+
+impl PipelineDesc {
+    pub fn new(vertex_shader: impl Into<PathBuf>, fragment_shader: impl Into<PathBuf>, layout: VertexLayout) -> Self {
+        Self {
+            vertex_shader: vertex_shader.into(),
+            fragment_shader: fragment_shader.into(),
+            layout,
+        }
+    }
+}
+
+/// Blending and depth state of a pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawState {
+    /// Blend with `SRC_ALPHA, ONE_MINUS_SRC_ALPHA` instead of overwriting.
+    pub blend: bool,
+    /// Keep only fragments nearer than what is there (`GL_LESS`).
+    pub depth_test: bool,
+    /// Store the depth of kept fragments. Like GL, only done while testing.
+    pub depth_write: bool,
+}
+
+impl DrawState {
+    /// State of the renderer's opaque pass.
+    pub const OPAQUE: DrawState = DrawState {
+        blend: false,
+        depth_test: true,
+        depth_write: true,
+    };
+    /// State of the renderer's blended pass.
+    pub const TRANSPARENT: DrawState = DrawState {
+        blend: true,
+        depth_test: true,
+        depth_write: false,
+    };
+    /// State of full-screen passes, which replace every pixel.
+    pub const OVERWRITE: DrawState = DrawState {
+        blend: false,
+        depth_test: false,
+        depth_write: false,
+    };
+}
+
+/// Everything drawn in one frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Where the frame is drawn; whatever framebuffer is bound when `None`,
+    /// normally the screen.
+    pub target: Option<TargetId>,
+    pub clear_color: [f32; 4],
+    pub view: Matrix4<f32>,
+    pub projection: Matrix4<f32>,
+    pub passes: Vec<Pass>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pass {
+    pub state: DrawState,
+    pub draws: Vec<DrawCall>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawCall {
+    pub pipeline: PipelineId,
+    pub texture: TextureId,
+    /// Places indexed geometry; instances carry their own transforms.
+    pub model: Matrix4<f32>,
+    pub geometry: Geometry,
+    /// Further shader inputs by name, set before drawing.
+    pub uniforms: Vec<(String, Uniform)>,
+}
+
+/// A value for a shader uniform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Uniform {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    /// A texture to sample, bound to a unit after the draw's own texture.
+    Texture(TextureId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Geometry {
+    /// Elements `first..first + count` of `elements`, indexing `vertices`.
+    Indexed {
+        vertices: BufferId,
+        elements: BufferId,
+        first: usize,
+        count: usize,
+    },
+    /// A unit quad for each of instances `first..first + count`.
+    Instanced {
+        instances: BufferId,
+        first: usize,
+        count: usize,
+    },
+    /// One triangle over the whole target, for `VertexLayout::FullScreen`.
+    FullScreen,
+}
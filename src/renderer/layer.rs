@@ -0,0 +1,100 @@
+use super::{MeshDraw, SpriteDraw};
+use crate::entity::Entity;
+
+/// Bands of the draw order, from back to front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Layer {
+    #[default]
+    Background,
+    Pipes,
+    Ground,
+    Bird,
+    Hud,
+    Overlay,
+}
+
+/// Component placing an entity's mesh or sprite in the draw order. Entities
+/// without one are drawn as opaque, in `Layer::Background` at `z` 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RenderLayer {
+    pub layer: Layer,
+    /// Order within the layer; higher is in front. Ties are broken by entity
+    /// so the order holds from frame to frame.
+    pub z: i32,
+    /// Has partly see-through pixels, so must be blended back to front after
+    /// the opaque pass instead of relying on the depth test alone.
+    pub transparent: bool,
+}
+
+impl RenderLayer {
+    pub fn new(layer: Layer) -> Self {
+        Self {
+            layer,
+            z: 0,
+            transparent: false,
+        }
+    }
+    pub fn with_z(mut self, z: i32) -> Self {
+        self.z = z;
+        self
+    }
+    pub fn transparent(mut self) -> Self {
+        self.transparent = true;
+        self
+    }
+}
+
+/// What the draw list is sorted by, back to front.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct DrawKey {
+    layer: Layer,
+    z: i32,
+    index: usize,
+    generation: u32,
+}
+
+impl DrawKey {
+    pub(crate) fn new(entity: Entity, layer: &RenderLayer) -> Self {
+        Self {
+            layer: layer.layer,
+            z: layer.z,
+            index: entity.index(),
+            generation: entity.generation(),
+        }
+    }
+}
+
+/// An entry of the sorted draw list: an index into the meshes or the sprites
+/// it was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Item {
+    Mesh(usize),
+    Sprite(usize),
+}
+
+/// Every mesh and sprite in draw order, back to front, each with whether it
+/// is blended.
+pub(crate) fn draw_order(meshes: &[MeshDraw], sprites: &[SpriteDraw]) -> Vec<(Item, bool)> {
+    let mut items: Vec<(DrawKey, bool, Item)> = meshes
+        .iter()
+        .enumerate()
+        .map(|(i, (entity, _, _, layer))| {
+            let layer = layer.copied().unwrap_or_default();
+            (DrawKey::new(*entity, &layer), layer.transparent, Item::Mesh(i))
+        })
+        .chain(sprites.iter().enumerate().map(|(i, (entity, _, layer))| {
+            let layer = layer.copied().unwrap_or_default();
+            (DrawKey::new(*entity, &layer), layer.transparent, Item::Sprite(i))
+        }))
+        .collect();
+    items.sort_by_key(|(key, _, _)| *key);
+    items.into_iter().map(|(_, transparent, item)| (item, transparent)).collect()
+}
+
+/// World y the `rank`th of `count` sorted items is drawn at. The camera looks
+/// down -y, so later items end up nearer to it and win the depth test.
+pub(crate) fn depth(rank: usize, count: usize) -> f32 {
+    const BACK: f32 = -64.0;
+    const FRONT: f32 = 2.0;
+    BACK + (FRONT - BACK) * (rank + 1) as f32 / (count + 1) as f32
+}
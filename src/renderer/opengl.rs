@@ -0,0 +1,418 @@
+use super::backend::{
+    Backend, BufferData, BufferId, BufferKind, DrawCall, DrawState, Frame, Geometry, PipelineDesc, PipelineId,
+    TargetId, TextureId, Uniform, VertexLayout,
+};
+use super::primatives::Vert;
+use super::sprites::{self, SpriteInstance};
+use super::target::{RenderTarget, TargetDesc, TargetError};
+use super::texture::Texture;
+use crate::image::Image;
+use crate::shader::Shader;
+use gl::{self, types::*};
+use std::any::Any;
+use std::collections::HashMap;
+
+/// A GL buffer object, with the vertex array that reads it for vertex and
+/// instance buffers.
+struct GlBuffer {
+    kind: BufferKind,
+    name: GLuint,
+    vao: GLuint,
+    /// Size the buffer is allocated at, in bytes.
+    capacity: usize,
+}
+
+/// `Backend` drawing with OpenGL 4.5 on whatever context is current.
+pub struct GlBackend {
+    next_id: u32,
+    buffers: HashMap<BufferId, GlBuffer>,
+    textures: HashMap<TextureId, Texture>,
+    pipelines: HashMap<PipelineId, (Shader, VertexLayout)>,
+    /// Targets, with the id their colour texture is sampled by.
+    targets: HashMap<TargetId, (RenderTarget, TextureId)>,
+    target_textures: HashMap<TextureId, TargetId>,
+    /// Vertex and element buffers of the unit quad instances are drawn over.
+    quad: Option<(GLuint, GLuint)>,
+    /// Vertex array with no attributes, for full-screen passes.
+    empty_vao: GLuint,
+}
+
+impl GlBackend {
+    pub fn new() -> Self {
+        Self {
+            next_id: 0,
+            buffers: HashMap::new(),
+            textures: HashMap::new(),
+            pipelines: HashMap::new(),
+            targets: HashMap::new(),
+            target_textures: HashMap::new(),
+            quad: None,
+            empty_vao: 0,
+        }
+    }
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+    fn unit_quad(&mut self) -> (GLuint, GLuint) {
+        *self.quad.get_or_insert_with(|| {
+            let (mut vbo, mut ebo) = (0, 0);
+            let vertices = vertex_data(&sprites::unit_quad());
+            let elements: [u32; 6] = [0, 1, 2, 0, 2, 3];
+            unsafe {
+                gl::GenBuffers(1, &mut vbo);
+                gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    std::mem::size_of_val(vertices.as_slice()) as isize,
+                    vertices.as_ptr() as *const std::ffi::c_void,
+                    gl::STATIC_DRAW,
+                );
+                gl::GenBuffers(1, &mut ebo);
+                gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+                // binding it as the element array would attach it to whatever
+                // vertex array is bound
+                gl::BindBuffer(gl::COPY_WRITE_BUFFER, ebo);
+                gl::BufferData(
+                    gl::COPY_WRITE_BUFFER,
+                    std::mem::size_of_val(&elements) as isize,
+                    elements.as_ptr() as *const std::ffi::c_void,
+                    gl::STATIC_DRAW,
+                );
+                gl::BindBuffer(gl::COPY_WRITE_BUFFER, 0);
+            }
+            (vbo, ebo)
+        })
+    }
+    fn set_state(state: DrawState) {
+        unsafe {
+            if state.blend {
+                gl::Enable(gl::BLEND);
+                gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            } else {
+                gl::Disable(gl::BLEND);
+            }
+            if state.depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            } else {
+                gl::Disable(gl::DEPTH_TEST);
+            }
+            gl::DepthMask(if state.depth_write { gl::TRUE } else { gl::FALSE });
+        }
+    }
+    fn texture(&self, texture: TextureId) -> &Texture {
+        match self.target_textures.get(&texture) {
+            Some(target) => &self.targets[target].0.color,
+            None => &self.textures[&texture],
+        }
+    }
+    fn draw(&self, draw: &DrawCall) {
+        match draw.geometry {
+            Geometry::Indexed {
+                vertices,
+                elements,
+                first,
+                count,
+            } => unsafe {
+                gl::BindVertexArray(self.buffers[&vertices].vao);
+                // the element binding is part of the vertex array's state
+                gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.buffers[&elements].name);
+                gl::DrawElements(
+                    gl::TRIANGLES,
+                    count as i32,
+                    gl::UNSIGNED_INT,
+                    (first * std::mem::size_of::<u32>()) as *const std::ffi::c_void,
+                );
+            },
+            Geometry::Instanced { instances, first, count } => unsafe {
+                gl::BindVertexArray(self.buffers[&instances].vao);
+                gl::DrawElementsInstancedBaseInstance(
+                    gl::TRIANGLES,
+                    6,
+                    gl::UNSIGNED_INT,
+                    std::ptr::null(),
+                    count as i32,
+                    first as u32,
+                );
+            },
+            Geometry::FullScreen => unsafe {
+                // the vertex shader makes the corners up from gl_VertexID
+                gl::BindVertexArray(self.empty_vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            },
+        }
+    }
+    fn set_uniforms(&self, shader: &Shader, uniforms: &[(String, Uniform)]) {
+        // unit 0 holds the draw's own texture
+        let mut unit = 1;
+        for (name, uniform) in uniforms {
+            match *uniform {
+                Uniform::Float(value) => shader.set_float(name, value),
+                Uniform::Vec2(value) => shader.set_vec2(name, value),
+                Uniform::Vec3(value) => shader.set_vec3(name, value),
+                Uniform::Vec4(value) => shader.set_vec4(name, value),
+                Uniform::Texture(texture) => {
+                    shader.set_tex_unit(name, self.texture(texture), unit);
+                    unit += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Default for GlBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for GlBackend {
+    fn create_buffer(&mut self, kind: BufferKind) -> BufferId {
+        let id = BufferId(self.next_id());
+        let (mut name, mut vao) = (0, 0);
+        unsafe {
+            gl::GenBuffers(1, &mut name);
+            match kind {
+                BufferKind::Vertex => {
+                    gl::GenVertexArrays(1, &mut vao);
+                    gl::BindVertexArray(vao);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, name);
+                    vert_attributes();
+                }
+                BufferKind::Instance => {
+                    let (quad_vbo, quad_ebo) = self.unit_quad();
+                    gl::GenVertexArrays(1, &mut vao);
+                    gl::BindVertexArray(vao);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, quad_vbo);
+                    vert_attributes();
+                    gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, quad_ebo);
+                    gl::BindBuffer(gl::ARRAY_BUFFER, name);
+                    instance_attributes();
+                }
+                BufferKind::Element => {}
+            }
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        self.buffers.insert(id, GlBuffer { kind, name, vao, capacity: 0 });
+        id
+    }
+    fn update_buffer(&mut self, buffer: BufferId, data: BufferData) {
+        let buffer = self.buffers.get_mut(&buffer).expect("no such buffer");
+        assert_eq!(buffer.kind, data.kind(), "buffer updated with the wrong kind of data");
+        unsafe {
+            gl::BindBuffer(gl::COPY_WRITE_BUFFER, buffer.name);
+            buffer.capacity = match data {
+                BufferData::Vertices(verts) => stream(gl::COPY_WRITE_BUFFER, &vertex_data(verts), buffer.capacity),
+                BufferData::Elements(elements) => stream(gl::COPY_WRITE_BUFFER, elements, buffer.capacity),
+                BufferData::Instances(instances) => {
+                    stream(gl::COPY_WRITE_BUFFER, &instance_data(instances), buffer.capacity)
+                }
+            };
+            gl::BindBuffer(gl::COPY_WRITE_BUFFER, 0);
+        }
+    }
+    fn destroy_buffer(&mut self, buffer: BufferId) {
+        if let Some(buffer) = self.buffers.remove(&buffer) {
+            delete_buffer(&buffer);
+        }
+    }
+    fn create_texture(&mut self, image: &Image) -> TextureId {
+        let id = TextureId(self.next_id());
+        self.textures.insert(id, Texture::from_image(image));
+        id
+    }
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> PipelineId {
+        let id = PipelineId(self.next_id());
+        if desc.layout == VertexLayout::FullScreen && self.empty_vao == 0 {
+            unsafe {
+                gl::GenVertexArrays(1, &mut self.empty_vao);
+            }
+        }
+        let shader = Shader::new(&desc.vertex_shader, &desc.fragment_shader);
+        self.pipelines.insert(id, (shader, desc.layout));
+        id
+    }
+    fn create_target(&mut self, desc: &TargetDesc) -> Result<TargetId, TargetError> {
+        let target = RenderTarget::new(desc)?;
+        let id = TargetId(self.next_id());
+        let texture = TextureId(self.next_id());
+        self.targets.insert(id, (target, texture));
+        self.target_textures.insert(texture, id);
+        Ok(id)
+    }
+    fn resize_target(&mut self, target: TargetId, width: u32, height: u32) {
+        self.targets.get_mut(&target).expect("no such target").0.resize(width, height);
+    }
+    fn destroy_target(&mut self, target: TargetId) {
+        if let Some((_, texture)) = self.targets.remove(&target) {
+            self.target_textures.remove(&texture);
+        }
+    }
+    fn target_texture(&self, target: TargetId) -> TextureId {
+        self.targets[&target].1
+    }
+    fn read_target(&mut self, target: TargetId) -> Image {
+        self.targets[&target].0.read_pixels()
+    }
+    fn submit(&mut self, frame: &Frame) {
+        // drawing into a target, then going back to what was bound before
+        let (mut framebuffer, mut viewport) = (0, [0; 4]);
+        if let Some(target) = frame.target {
+            unsafe {
+                gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut framebuffer);
+                gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            }
+            self.targets[&target].0.bind();
+        }
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            let [r, g, b, a] = frame.clear_color;
+            gl::ClearColor(r, g, b, a);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+        for pass in &frame.passes {
+            Self::set_state(pass.state);
+            let mut bound = None;
+            for draw in &pass.draws {
+                let (shader, _) = &self.pipelines[&draw.pipeline];
+                if bound != Some((draw.pipeline, draw.texture)) {
+                    shader.enable();
+                    shader.set_tex("tex", self.texture(draw.texture));
+                    shader.set_mat4("view", &frame.view);
+                    shader.set_mat4("cam", &frame.projection);
+                    bound = Some((draw.pipeline, draw.texture));
+                }
+                shader.set_mat4("model", &draw.model);
+                self.set_uniforms(shader, &draw.uniforms);
+                self.draw(draw);
+            }
+        }
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            gl::BindVertexArray(0);
+            if frame.target.is_some() {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer as GLuint);
+                let [x, y, width, height] = viewport;
+                gl::Viewport(x, y, width, height);
+            }
+        }
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Drop for GlBackend {
+    fn drop(&mut self) {
+        for buffer in self.buffers.values() {
+            delete_buffer(buffer);
+        }
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, &texture.id);
+            }
+            for (shader, _) in self.pipelines.values() {
+                gl::DeleteProgram(shader.id);
+            }
+            if let Some((vbo, ebo)) = self.quad {
+                gl::DeleteBuffers(1, &vbo);
+                gl::DeleteBuffers(1, &ebo);
+            }
+            if self.empty_vao != 0 {
+                gl::DeleteVertexArrays(1, &self.empty_vao);
+            }
+        }
+    }
+}
+
+fn delete_buffer(buffer: &GlBuffer) {
+    unsafe {
+        if buffer.vao != 0 {
+            gl::DeleteVertexArrays(1, &buffer.vao);
+        }
+        gl::DeleteBuffers(1, &buffer.name);
+    }
+}
+
+/// Interleave `verts` into the layout described by `vert_attributes`.
+fn vertex_data(verts: &[Vert]) -> Vec<f32> {
+    verts
+        .iter()
+        .flat_map(|v| {
+            [
+                v.pos.x, v.pos.y, v.pos.z, v.color.x, v.color.y, v.color.z, v.uv.x, v.uv.y,
+                v.normal.x, v.normal.y, v.normal.z,
+            ]
+        })
+        .collect()
+}
+
+/// Floats per instance: a 4x4 matrix, a uv rectangle and a tint.
+const INSTANCE_FLOATS: usize = 16 + 4 + 4;
+
+fn instance_data(instances: &[SpriteInstance]) -> Vec<f32> {
+    let mut data = Vec::with_capacity(instances.len() * INSTANCE_FLOATS);
+    for instance in instances {
+        data.extend_from_slice(instance.model.as_slice());
+        data.extend_from_slice(&[instance.uv_min.x, instance.uv_min.y, instance.uv_max.x, instance.uv_max.y]);
+        data.extend_from_slice(&instance.tint);
+    }
+    data
+}
+
+/// Describe the `Vert` layout of the bound array buffer to the bound vertex
+/// array: position, color, uv, normal.
+unsafe fn vert_attributes() {
+    let stride = (11 * std::mem::size_of::<f32>()) as i32;
+    for (location, size, offset) in [(0, 3, 0), (1, 3, 3), (2, 2, 6), (3, 3, 8)] {
+        gl::VertexAttribPointer(
+            location,
+            size,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            (offset * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
+        );
+        gl::EnableVertexAttribArray(location);
+    }
+}
+
+/// Describe the bound array buffer as `SpriteInstance`s, advancing once per
+/// instance: the model matrix one column per attribute, then uv rect and
+/// tint.
+unsafe fn instance_attributes() {
+    let stride = (INSTANCE_FLOATS * std::mem::size_of::<f32>()) as i32;
+    for i in 0..6 {
+        let location = 4 + i;
+        gl::VertexAttribPointer(
+            location,
+            4,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            (4 * i as usize * std::mem::size_of::<f32>()) as *const std::ffi::c_void,
+        );
+        gl::EnableVertexAttribArray(location);
+        gl::VertexAttribDivisor(location, 1);
+    }
+}
+
+/// Smallest buffer allocation, in bytes.
+const MIN_BUFFER_SIZE: usize = 4096;
+
+/// Write `data` to the buffer bound to `target`, which is `capacity` bytes
+/// long. If it doesn't fit, the buffer is reallocated at double the size
+/// (or more, to fit) first. Returns the buffer's capacity afterwards.
+unsafe fn stream<T>(target: GLenum, data: &[T], capacity: usize) -> usize {
+    let size = std::mem::size_of_val(data);
+    let mut capacity = capacity;
+    if size > capacity {
+        capacity = size.max(capacity * 2).max(MIN_BUFFER_SIZE);
+        gl::BufferData(target, capacity as isize, std::ptr::null(), gl::DYNAMIC_DRAW);
+    }
+    if size > 0 {
+        gl::BufferSubData(target, 0, size as isize, data.as_ptr() as *const std::ffi::c_void);
+    }
+    capacity
+}
@@ -0,0 +1,227 @@
+use super::backend::{
+    DrawCall, DrawState, Frame, Geometry, Pass, PipelineDesc, PipelineId, TargetId, TextureId, Uniform, VertexLayout,
+};
+use super::target::TargetDesc;
+use super::texture::TextureFormat;
+use super::{Renderer, CLEAR_COLOR};
+use crate::camera::Camera;
+use crate::image::Image;
+use crate::scene::Scene;
+use crate::time::Time;
+use colored::Colorize;
+use nalgebra::Matrix4;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Vertex shader every effect is drawn with.
+pub const FULL_SCREEN_VS: &str = "shaders/post/fullscreen.vs";
+
+/// An effect parameter as written in the config: a number, a list of up to
+/// four numbers, or the path of an image to sample.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum Param {
+    Float(f32),
+    Vector(Vec<f32>),
+    Texture(PathBuf),
+}
+
+/// A full-screen pass: a fragment shader run over the frame so far.
+///
+/// The shader reads the frame from the sampler `tex` at `o_uv`, and gets the
+/// target size in pixels as `resolution`, seconds of game time as `time`,
+/// and each parameter as a uniform of the same name.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Effect {
+    pub name: String,
+    /// Path of the fragment shader.
+    pub shader: PathBuf,
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub params: BTreeMap<String, Param>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+impl Effect {
+    pub fn new(name: impl Into<String>, shader: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            shader: shader.into(),
+            enabled: true,
+            params: BTreeMap::new(),
+        }
+    }
+    pub fn with_param(mut self, name: impl Into<String>, value: Param) -> Self {
+        self.params.insert(name.into(), value);
+        self
+    }
+    pub fn disabled(mut self) -> Self {
+        self.enabled = false;
+        self
+    }
+}
+
+/// Resource running `Effect`s over each frame, in order.
+///
+/// The scene is drawn into an offscreen target, each enabled effect reads the
+/// result of the one before, and the last draws where the renderer would
+/// have. With no effect enabled the scene is drawn there directly.
+#[derive(Debug, Default)]
+pub struct PostChain {
+    pub effects: Vec<Effect>,
+    /// The pair of targets passes take turns reading and writing, and the
+    /// size they were made at. Only the first, which the scene is drawn
+    /// into, has a depth buffer.
+    targets: Option<([TargetId; 2], (u32, u32))>,
+    pipelines: HashMap<PathBuf, PipelineId>,
+    /// Images loaded for `Param::Texture`s; `None` if one couldn't be.
+    textures: HashMap<PathBuf, Option<TextureId>>,
+}
+
+impl PostChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_effects(effects: Vec<Effect>) -> Self {
+        Self {
+            effects,
+            ..Self::default()
+        }
+    }
+    pub fn effect(&self, name: &str) -> Option<&Effect> {
+        self.effects.iter().find(|e| e.name == name)
+    }
+    pub fn effect_mut(&mut self, name: &str) -> Option<&mut Effect> {
+        self.effects.iter_mut().find(|e| e.name == name)
+    }
+    /// Turn the effect `name` on or off. Returns whether there is one.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        self.effect_mut(name).map(|e| e.enabled = enabled).is_some()
+    }
+    /// Flip the effect `name` on or off, returning whether it is now on.
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let effect = self.effect_mut(name)?;
+        effect.enabled = !effect.enabled;
+        Some(effect.enabled)
+    }
+    /// Set a parameter of the effect `effect`. Returns whether there is one.
+    pub fn set_param(&mut self, effect: &str, name: &str, value: Param) -> bool {
+        self.effect_mut(effect).map(|e| e.params.insert(name.to_string(), value)).is_some()
+    }
+    pub fn is_active(&self) -> bool {
+        self.effects.iter().any(|e| e.enabled)
+    }
+
+    /// Draw the scene with `Renderer::newrender`, then run the enabled
+    /// effects over it. The targets follow the `Camera`'s size.
+    pub fn render(&mut self, renderer: &mut Renderer, scene: &Scene) {
+        let effects: Vec<Effect> = self.effects.iter().filter(|e| e.enabled).cloned().collect();
+        if effects.is_empty() {
+            renderer.newrender(scene);
+            return;
+        }
+        let (width, height) = scene.resource::<Camera>().unwrap_or(&renderer.camera).size();
+        let targets = self.targets(renderer, width, height);
+        let output = renderer.target;
+        renderer.target = Some(targets[0]);
+        renderer.newrender(scene);
+        renderer.target = output;
+
+        let time = scene.resource::<Time>().map_or(0.0, |t| t.elapsed.as_secs_f32());
+        for (i, effect) in effects.iter().enumerate() {
+            let source = targets[i % 2];
+            let target = if i + 1 == effects.len() { output } else { Some(targets[(i + 1) % 2]) };
+            let mut uniforms = vec![
+                ("resolution".to_string(), Uniform::Vec2([width as f32, height as f32])),
+                ("time".to_string(), Uniform::Float(time)),
+            ];
+            for (name, param) in &effect.params {
+                if let Some(uniform) = self.uniform(renderer, param) {
+                    uniforms.push((name.clone(), uniform));
+                }
+            }
+            let draw = DrawCall {
+                pipeline: self.pipeline(renderer, &effect.shader),
+                texture: renderer.backend_mut().target_texture(source),
+                model: Matrix4::identity(),
+                geometry: Geometry::FullScreen,
+                uniforms,
+            };
+            renderer.backend_mut().submit(&Frame {
+                target,
+                clear_color: CLEAR_COLOR,
+                view: Matrix4::identity(),
+                projection: Matrix4::identity(),
+                passes: vec![Pass {
+                    state: DrawState::OVERWRITE,
+                    draws: vec![draw],
+                }],
+            });
+        }
+    }
+
+    /// The chain's targets at `width` by `height`, made on first use and
+    /// resized when the window is.
+    fn targets(&mut self, renderer: &mut Renderer, width: u32, height: u32) -> [TargetId; 2] {
+        let backend = renderer.backend_mut();
+        match &mut self.targets {
+            Some((targets, size)) => {
+                if *size != (width, height) {
+                    for target in *targets {
+                        backend.resize_target(target, width, height);
+                    }
+                    *size = (width, height);
+                }
+                *targets
+            }
+            None => {
+                let scene = TargetDesc::new(width, height).with_depth(TextureFormat::DepthComponent);
+                let targets = [&scene, &TargetDesc::new(width, height)].map(|desc| {
+                    backend
+                        .create_target(desc)
+                        .unwrap_or_else(|e| panic!("could not make a post-processing target: {}", e))
+                });
+                self.targets = Some((targets, (width, height)));
+                targets
+            }
+        }
+    }
+
+    fn pipeline(&mut self, renderer: &mut Renderer, shader: &Path) -> PipelineId {
+        *self.pipelines.entry(shader.to_path_buf()).or_insert_with(|| {
+            let desc = PipelineDesc::new(FULL_SCREEN_VS, shader, VertexLayout::FullScreen);
+            renderer.backend_mut().create_pipeline(&desc)
+        })
+    }
+
+    /// `param` as a uniform, or `None` for a list of the wrong length or an
+    /// image that can't be loaded.
+    fn uniform(&mut self, renderer: &mut Renderer, param: &Param) -> Option<Uniform> {
+        match param {
+            Param::Float(value) => Some(Uniform::Float(*value)),
+            Param::Vector(values) => match values[..] {
+                [x] => Some(Uniform::Float(x)),
+                [x, y] => Some(Uniform::Vec2([x, y])),
+                [x, y, z] => Some(Uniform::Vec3([x, y, z])),
+                [x, y, z, w] => Some(Uniform::Vec4([x, y, z, w])),
+                _ => None,
+            },
+            Param::Texture(path) => {
+                let texture = self.textures.entry(path.clone()).or_insert_with(|| match Image::from_path(path) {
+                    Ok(image) => Some(renderer.backend_mut().create_texture(&image)),
+                    Err(e) => {
+                        eprintln!("{} could not load {}: {}", "warning:".bold().red(), path.display(), e);
+                        None
+                    }
+                });
+                texture.map(Uniform::Texture)
+            }
+        }
+    }
+}
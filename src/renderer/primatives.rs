@@ -60,7 +60,7 @@ pub struct Quad {
 impl Quad {
     pub fn new(verts: [Vert; 4]) -> Quad {
         Quad {
-            verts: verts,
+            verts,
             elements: [0, 1, 2, 0, 2, 3],
         }
     }
@@ -108,27 +108,29 @@ pub struct Cube {
     pub quads: [Quad; 6],
 }
 
+impl Default for Cube {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Cube {
     pub fn verts(&self) -> Vec<Vert> {
-        self.quads.iter().map(|q| q.verts).flatten().collect()
+        self.quads.iter().flat_map(|q| q.verts).collect()
     }
     pub fn elements(&self) -> Vec<u32> {
         let mut offset = 0;
         self.quads
             .iter()
-            .map(|q| {
+            .flat_map(|q| {
                 let a = q
                     .elements
                     .iter()
-                    .map(|e| {
-                        let x = e + offset;
-                        x
-                    })
+                    .map(|e| e + offset)
                     .collect::<Vec<u32>>();
                 offset += 4;
                 a
             })
-            .flatten()
             .collect()
     }
     pub fn new() -> Cube {
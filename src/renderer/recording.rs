@@ -0,0 +1,166 @@
+use super::backend::{
+    Backend, BufferData, BufferId, BufferKind, Frame, PipelineDesc, PipelineId, TargetId, TextureId,
+};
+use super::primatives::Vert;
+use super::sprites::SpriteInstance;
+use super::target::{TargetDesc, TargetError};
+use crate::image::Image;
+use std::any::Any;
+use std::collections::HashMap;
+
+/// A request made of a `RecordingBackend`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    CreateBuffer(BufferId, BufferKind),
+    /// A buffer given this many vertices, elements or instances.
+    UpdateBuffer(BufferId, usize),
+    DestroyBuffer(BufferId),
+    /// A texture of an image this wide and high.
+    CreateTexture(TextureId, u32, u32),
+    CreatePipeline(PipelineId, PipelineDesc),
+    CreateTarget(TargetId, TargetDesc),
+    /// A target resized to this width and height.
+    ResizeTarget(TargetId, u32, u32),
+    DestroyTarget(TargetId),
+    Submit(Frame),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Contents {
+    Vertices(Vec<Vert>),
+    Elements(Vec<u32>),
+    Instances(Vec<SpriteInstance>),
+}
+
+/// `Backend` that draws nothing, keeping every call made of it and what each
+/// buffer holds, so render logic can be checked without a GPU.
+#[derive(Debug, Default)]
+pub struct RecordingBackend {
+    pub calls: Vec<Call>,
+    next_id: u32,
+    buffers: HashMap<BufferId, Contents>,
+    /// Live targets and the texture each is sampled by.
+    targets: HashMap<TargetId, (TargetDesc, TextureId)>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Every frame submitted, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &Frame> {
+        self.calls.iter().filter_map(|call| match call {
+            Call::Submit(frame) => Some(frame),
+            _ => None,
+        })
+    }
+    pub fn last_frame(&self) -> Option<&Frame> {
+        self.frames().last()
+    }
+    /// Whether `buffer` has been created and not yet destroyed.
+    pub fn is_live(&self, buffer: BufferId) -> bool {
+        self.buffers.contains_key(&buffer)
+    }
+    /// Number of buffers created and not yet destroyed.
+    pub fn live_buffers(&self) -> usize {
+        self.buffers.len()
+    }
+    /// The size and formats `target` has now, if it is live.
+    pub fn target(&self, target: TargetId) -> Option<&TargetDesc> {
+        self.targets.get(&target).map(|(desc, _)| desc)
+    }
+    pub fn vertices(&self, buffer: BufferId) -> Option<&[Vert]> {
+        match self.buffers.get(&buffer)? {
+            Contents::Vertices(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub fn elements(&self, buffer: BufferId) -> Option<&[u32]> {
+        match self.buffers.get(&buffer)? {
+            Contents::Elements(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub fn instances(&self, buffer: BufferId) -> Option<&[SpriteInstance]> {
+        match self.buffers.get(&buffer)? {
+            Contents::Instances(data) => Some(data),
+            _ => None,
+        }
+    }
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+impl Backend for RecordingBackend {
+    fn create_buffer(&mut self, kind: BufferKind) -> BufferId {
+        let id = BufferId(self.next_id());
+        let contents = match kind {
+            BufferKind::Vertex => Contents::Vertices(Vec::new()),
+            BufferKind::Element => Contents::Elements(Vec::new()),
+            BufferKind::Instance => Contents::Instances(Vec::new()),
+        };
+        self.buffers.insert(id, contents);
+        self.calls.push(Call::CreateBuffer(id, kind));
+        id
+    }
+    fn update_buffer(&mut self, buffer: BufferId, data: BufferData) {
+        let contents = self.buffers.get_mut(&buffer).expect("no such buffer");
+        *contents = match (&contents, data) {
+            (Contents::Vertices(_), BufferData::Vertices(data)) => Contents::Vertices(data.to_vec()),
+            (Contents::Elements(_), BufferData::Elements(data)) => Contents::Elements(data.to_vec()),
+            (Contents::Instances(_), BufferData::Instances(data)) => Contents::Instances(data.to_vec()),
+            _ => panic!("buffer updated with the wrong kind of data"),
+        };
+        self.calls.push(Call::UpdateBuffer(buffer, data.len()));
+    }
+    fn destroy_buffer(&mut self, buffer: BufferId) {
+        self.buffers.remove(&buffer);
+        self.calls.push(Call::DestroyBuffer(buffer));
+    }
+    fn create_texture(&mut self, image: &Image) -> TextureId {
+        let id = TextureId(self.next_id());
+        self.calls.push(Call::CreateTexture(id, image.width, image.height));
+        id
+    }
+    fn create_pipeline(&mut self, desc: &PipelineDesc) -> PipelineId {
+        let id = PipelineId(self.next_id());
+        self.calls.push(Call::CreatePipeline(id, desc.clone()));
+        id
+    }
+    fn create_target(&mut self, desc: &TargetDesc) -> Result<TargetId, TargetError> {
+        desc.validate()?;
+        let id = TargetId(self.next_id());
+        let texture = TextureId(self.next_id());
+        self.targets.insert(id, (*desc, texture));
+        self.calls.push(Call::CreateTarget(id, *desc));
+        Ok(id)
+    }
+    fn resize_target(&mut self, target: TargetId, width: u32, height: u32) {
+        let (desc, _) = self.targets.get_mut(&target).expect("no such target");
+        if width != 0 && height != 0 {
+            desc.width = width;
+            desc.height = height;
+        }
+        self.calls.push(Call::ResizeTarget(target, width, height));
+    }
+    fn destroy_target(&mut self, target: TargetId) {
+        self.targets.remove(&target);
+        self.calls.push(Call::DestroyTarget(target));
+    }
+    fn target_texture(&self, target: TargetId) -> TextureId {
+        self.targets[&target].1
+    }
+    /// Nothing is drawn, so this is transparent black at the target's size.
+    fn read_target(&mut self, target: TargetId) -> Image {
+        let (desc, _) = &self.targets[&target];
+        Image::new(desc.width, desc.height, vec![0; (desc.width * desc.height * 4) as usize])
+    }
+    fn submit(&mut self, frame: &Frame) {
+        self.calls.push(Call::Submit(frame.clone()));
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
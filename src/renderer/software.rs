@@ -0,0 +1,405 @@
+use super::backend::{
+    Backend, BufferData, BufferId, BufferKind, DrawCall, DrawState, Frame, Geometry, PipelineDesc, PipelineId,
+    TargetId, TextureId,
+};
+use super::primatives::Vert;
+use super::sprites::{self, SpriteInstance};
+use super::target::{TargetDesc, TargetError};
+use crate::image::Image;
+use nalgebra::{Matrix4, Point2, Point3, Vector4};
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Screen-space vertex of a triangle being rasterized. `x` and `y` are in
+/// pixels from the bottom left, `z` is depth from 0 to 1, and the uvs are
+/// divided by `w` for perspective-correct interpolation.
+#[derive(Debug, Clone, Copy)]
+struct ScreenVert {
+    x: f32,
+    y: f32,
+    z: f32,
+    inv_w: f32,
+    u_w: f32,
+    v_w: f32,
+}
+
+/// A colour and depth buffer triangles are rasterized into.
+#[derive(Debug, Clone, Default)]
+struct Canvas {
+    width: u32,
+    height: u32,
+    /// RGBA, bottom row first like a GL framebuffer.
+    color: Vec<[u8; 4]>,
+    depth: Vec<f32>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Self {
+        let size = (width * height) as usize;
+        Self {
+            width,
+            height,
+            color: vec![[0, 0, 0, 0]; size],
+            depth: vec![1.0; size],
+        }
+    }
+    fn clear(&mut self, color: [f32; 4]) {
+        self.color.fill(color.map(to_byte));
+        self.depth.fill(1.0);
+    }
+    /// The colour buffer, top row first.
+    fn image(&self) -> Image {
+        let rows = self.color.chunks(self.width as usize).rev();
+        Image::new(self.width, self.height, rows.flatten().flatten().copied().collect())
+    }
+    /// The colour buffer as a texture samples it, bottom row at v = 0.
+    fn texture(&self) -> Image {
+        Image::new(self.width, self.height, self.color.iter().flatten().copied().collect())
+    }
+    fn draw(
+        &mut self,
+        verts: &[Vert],
+        elements: &[u32],
+        transform: &Matrix4<f32>,
+        texture: &Image,
+        tint: [f32; 4],
+        state: DrawState,
+    ) {
+        let screen: Vec<Option<ScreenVert>> = verts.iter().map(|v| self.to_screen(v, transform)).collect();
+        for triangle in elements.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|i| screen[triangle[i] as usize]);
+            if let [Some(a), Some(b), Some(c)] = corners {
+                self.triangle(a, b, c, texture, tint, state);
+            }
+        }
+    }
+    fn to_screen(&self, vert: &Vert, transform: &Matrix4<f32>) -> Option<ScreenVert> {
+        let clip = transform * Vector4::new(vert.pos.x, vert.pos.y, vert.pos.z, 1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let inv_w = 1.0 / clip.w;
+        Some(ScreenVert {
+            x: (clip.x * inv_w + 1.0) * 0.5 * self.width as f32,
+            y: (clip.y * inv_w + 1.0) * 0.5 * self.height as f32,
+            z: (clip.z * inv_w + 1.0) * 0.5,
+            inv_w,
+            u_w: vert.uv.x * inv_w,
+            v_w: vert.uv.y * inv_w,
+        })
+    }
+
+    fn triangle(
+        &mut self,
+        a: ScreenVert,
+        mut b: ScreenVert,
+        mut c: ScreenVert,
+        texture: &Image,
+        tint: [f32; 4],
+        state: DrawState,
+    ) {
+        let mut area = edge(&a, &b, c.x, c.y);
+        if area == 0.0 {
+            return;
+        }
+        // wind counter-clockwise so every edge function is positive inside
+        if area < 0.0 {
+            std::mem::swap(&mut b, &mut c);
+            area = -area;
+        }
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as u32;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as u32;
+        let max_x = (a.x.max(b.x).max(c.x).ceil() as u32).min(self.width);
+        let max_y = (a.y.max(b.y).max(c.y).ceil() as u32).min(self.height);
+        let edges = [(b, c), (c, a), (a, b)];
+        let top_left = edges.map(|(from, to)| is_top_left(&from, &to));
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = edges.map(|(from, to)| edge(&from, &to, px, py));
+                let inside = weights
+                    .iter()
+                    .zip(top_left)
+                    .all(|(&w, top_left)| w > 0.0 || (w == 0.0 && top_left));
+                if !inside {
+                    continue;
+                }
+                let [la, lb, lc] = weights.map(|w| w / area);
+                let z = la * a.z + lb * b.z + lc * c.z;
+                if !(0.0..=1.0).contains(&z) {
+                    continue;
+                }
+                let i = (y * self.width + x) as usize;
+                if state.depth_test && z >= self.depth[i] {
+                    continue;
+                }
+                let inv_w = la * a.inv_w + lb * b.inv_w + lc * c.inv_w;
+                let u = (la * a.u_w + lb * b.u_w + lc * c.u_w) / inv_w;
+                let v = (la * a.v_w + lb * b.v_w + lc * c.v_w) / inv_w;
+                let texel = sample(texture, u, v);
+                let src: [f32; 4] = std::array::from_fn(|k| texel[k] as f32 / 255.0 * tint[k]);
+                if src[3] < 0.1 {
+                    continue;
+                }
+                self.color[i] = if state.blend {
+                    let dst = self.color[i].map(|c| c as f32 / 255.0);
+                    std::array::from_fn(|k| to_byte(src[k] * src[3] + dst[k] * (1.0 - src[3])))
+                } else {
+                    src.map(to_byte)
+                };
+                if state.depth_test && state.depth_write {
+                    self.depth[i] = z;
+                }
+            }
+        }
+    }
+}
+
+/// A target's size and formats, what was drawn into it, and the texture it
+/// is sampled by.
+struct Target {
+    desc: TargetDesc,
+    canvas: Canvas,
+    texture: TextureId,
+}
+
+/// What a buffer was last given.
+enum Contents {
+    Vertices(Vec<Vert>),
+    Elements(Vec<u32>),
+    Instances(Vec<SpriteInstance>),
+}
+
+/// `Backend` drawing on the CPU with the same results as `GlBackend`:
+/// indexed triangles of `Vert`s, nearest-filtered repeating texture lookups,
+/// fragments under 0.1 alpha discarded as in `shaders/hello.fs`, alpha
+/// blending and a depth test. Frames without a target are drawn into its
+/// screen buffer.
+///
+/// Pixel centres, the top-left fill rule and depth range follow GL, so the
+/// output matches a GL render up to rounding. Triangles with a vertex behind
+/// the camera are skipped rather than clipped; the near and far planes are
+/// applied per fragment. Shaders aren't run: meshes show their texels,
+/// sprites their texels times their tint, and full-screen draws copy their
+/// texture, so post effects are left out.
+pub struct SoftwareRenderer {
+    screen: Canvas,
+    next_id: u32,
+    buffers: HashMap<BufferId, Contents>,
+    textures: HashMap<TextureId, Image>,
+    targets: HashMap<TargetId, Target>,
+}
+
+impl SoftwareRenderer {
+    /// A renderer with a `width` by `height` screen.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            screen: Canvas::new(width, height),
+            next_id: 0,
+            buffers: HashMap::new(),
+            textures: HashMap::new(),
+            targets: HashMap::new(),
+        }
+    }
+    /// Reallocate the screen at a new size, losing what it held.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.screen = Canvas::new(width, height);
+    }
+    /// Fill the screen with `color` and reset depth to the far plane.
+    pub fn clear(&mut self, color: [f32; 4]) {
+        self.screen.clear(color);
+    }
+    /// Draw the triangles `elements` index out of `verts` on the screen,
+    /// transformed to clip space by `transform`. Texels are multiplied by
+    /// `tint`; vertex colours are ignored, as the GL shaders do.
+    pub fn draw(
+        &mut self,
+        verts: &[Vert],
+        elements: &[u32],
+        transform: &Matrix4<f32>,
+        texture: &Image,
+        tint: [f32; 4],
+        state: DrawState,
+    ) {
+        self.screen.draw(verts, elements, transform, texture, tint, state);
+    }
+    /// The screen, top row first.
+    pub fn image(&self) -> Image {
+        self.screen.image()
+    }
+
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+    /// The image `texture` samples, reading targets as they are now.
+    fn texture(&self, texture: TextureId) -> Cow<'_, Image> {
+        match self.textures.get(&texture) {
+            Some(image) => Cow::Borrowed(image),
+            None => {
+                let target = self.targets.values().find(|t| t.texture == texture).expect("no such texture");
+                Cow::Owned(target.canvas.texture())
+            }
+        }
+    }
+    fn draw_call(&self, canvas: &mut Canvas, draw: &DrawCall, view_projection: &Matrix4<f32>, state: DrawState) {
+        let texture = self.texture(draw.texture);
+        match draw.geometry {
+            Geometry::Indexed { vertices, elements, first, count } => {
+                let (Some(Contents::Vertices(verts)), Some(Contents::Elements(indices))) =
+                    (self.buffers.get(&vertices), self.buffers.get(&elements))
+                else {
+                    panic!("indexed draw without vertex and element buffers");
+                };
+                let transform = view_projection * draw.model;
+                canvas.draw(verts, &indices[first..first + count], &transform, &texture, [1.0; 4], state);
+            }
+            Geometry::Instanced { instances, first, count } => {
+                let Some(Contents::Instances(instances)) = self.buffers.get(&instances) else {
+                    panic!("instanced draw without an instance buffer");
+                };
+                for instance in &instances[first..first + count] {
+                    let (quad, transform) = (sprite_quad(instance), view_projection * instance.model);
+                    canvas.draw(&quad, &[0, 1, 2, 0, 2, 3], &transform, &texture, instance.tint, state);
+                }
+            }
+            Geometry::FullScreen => {
+                let corner = |x: f32, y: f32| {
+                    Vert::new(
+                        Point3::new(x * 2.0 - 1.0, y * 2.0 - 1.0, 0.0),
+                        Point3::new(1.0, 1.0, 1.0),
+                        Point2::new(x, y),
+                        Point3::new(0.0, 0.0, 0.0),
+                    )
+                };
+                let quad = [corner(0.0, 0.0), corner(1.0, 0.0), corner(1.0, 1.0), corner(0.0, 1.0)];
+                canvas.draw(&quad, &[0, 1, 2, 0, 2, 3], &Matrix4::identity(), &texture, [1.0; 4], state);
+            }
+        }
+    }
+}
+
+impl Backend for SoftwareRenderer {
+    fn create_buffer(&mut self, kind: BufferKind) -> BufferId {
+        let id = BufferId(self.next_id());
+        let contents = match kind {
+            BufferKind::Vertex => Contents::Vertices(Vec::new()),
+            BufferKind::Element => Contents::Elements(Vec::new()),
+            BufferKind::Instance => Contents::Instances(Vec::new()),
+        };
+        self.buffers.insert(id, contents);
+        id
+    }
+    fn update_buffer(&mut self, buffer: BufferId, data: BufferData) {
+        let contents = self.buffers.get_mut(&buffer).expect("no such buffer");
+        *contents = match (&contents, data) {
+            (Contents::Vertices(_), BufferData::Vertices(data)) => Contents::Vertices(data.to_vec()),
+            (Contents::Elements(_), BufferData::Elements(data)) => Contents::Elements(data.to_vec()),
+            (Contents::Instances(_), BufferData::Instances(data)) => Contents::Instances(data.to_vec()),
+            _ => panic!("buffer updated with the wrong kind of data"),
+        };
+    }
+    fn destroy_buffer(&mut self, buffer: BufferId) {
+        self.buffers.remove(&buffer);
+    }
+    fn create_texture(&mut self, image: &Image) -> TextureId {
+        let id = TextureId(self.next_id());
+        self.textures.insert(id, image.clone());
+        id
+    }
+    /// Shaders aren't run, so pipelines are only ids.
+    fn create_pipeline(&mut self, _desc: &PipelineDesc) -> PipelineId {
+        PipelineId(self.next_id())
+    }
+    fn create_target(&mut self, desc: &TargetDesc) -> Result<TargetId, TargetError> {
+        desc.validate()?;
+        let id = TargetId(self.next_id());
+        let texture = TextureId(self.next_id());
+        let canvas = Canvas::new(desc.width, desc.height);
+        self.targets.insert(id, Target { desc: *desc, canvas, texture });
+        Ok(id)
+    }
+    fn resize_target(&mut self, target: TargetId, width: u32, height: u32) {
+        let target = self.targets.get_mut(&target).expect("no such target");
+        if width != 0 && height != 0 {
+            target.desc.width = width;
+            target.desc.height = height;
+            target.canvas = Canvas::new(width, height);
+        }
+    }
+    fn destroy_target(&mut self, target: TargetId) {
+        self.targets.remove(&target);
+    }
+    fn target_texture(&self, target: TargetId) -> TextureId {
+        self.targets[&target].texture
+    }
+    fn read_target(&mut self, target: TargetId) -> Image {
+        self.targets[&target].canvas.image()
+    }
+    fn submit(&mut self, frame: &Frame) {
+        // taken out while drawing, so textures can still be looked up
+        let (mut canvas, has_depth) = match frame.target {
+            Some(target) => {
+                let target = self.targets.get_mut(&target).expect("no such target");
+                (std::mem::take(&mut target.canvas), target.desc.depth.is_some())
+            }
+            None => (std::mem::take(&mut self.screen), true),
+        };
+        canvas.clear(frame.clear_color);
+        let view_projection = frame.projection * frame.view;
+        for pass in &frame.passes {
+            // like GL, a target without a depth buffer keeps every fragment
+            let state = DrawState {
+                depth_test: pass.state.depth_test && has_depth,
+                ..pass.state
+            };
+            for draw in &pass.draws {
+                self.draw_call(&mut canvas, draw, &view_projection, state);
+            }
+        }
+        match frame.target {
+            Some(target) => self.targets.get_mut(&target).unwrap().canvas = canvas,
+            None => self.screen = canvas,
+        }
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// The unit quad with its uvs spread over the instance's uv rectangle, as
+/// `shaders/sprite.vs` does.
+fn sprite_quad(instance: &SpriteInstance) -> [Vert; 4] {
+    let (min, max) = (instance.uv_min, instance.uv_max);
+    sprites::unit_quad().map(|mut v| {
+        v.uv = Point2::new(min.x + (max.x - min.x) * v.uv.x, min.y + (max.y - min.y) * v.uv.y);
+        v
+    })
+}
+
+/// Twice the signed area of the triangle `from`, `to`, (`x`, `y`); positive
+/// when the point is to the left of the edge.
+fn edge(from: &ScreenVert, to: &ScreenVert, x: f32, y: f32) -> f32 {
+    (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+}
+
+/// Whether pixels exactly on an edge of a counter-clockwise triangle belong to
+/// it: those on its top or left edges do, so shared edges are drawn once.
+fn is_top_left(from: &ScreenVert, to: &ScreenVert) -> bool {
+    let top = from.y == to.y && to.x < from.x;
+    let left = to.y < from.y;
+    top || left
+}
+
+/// Nearest texel at (`u`, `v`), repeating outside 0..1. Row 0 is the top of
+/// the image, as it is for a texture uploaded from it.
+fn sample(texture: &Image, u: f32, v: f32) -> [u8; 4] {
+    let x = ((u * texture.width as f32).floor() as i64).rem_euclid(texture.width as i64) as u32;
+    let y = ((v * texture.height as f32).floor() as i64).rem_euclid(texture.height as i64) as u32;
+    texture.pixel(x, y)
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
@@ -0,0 +1,47 @@
+use super::primatives::Vert;
+use crate::components::Sprite;
+use nalgebra::{Matrix4, Point2, Point3};
+
+/// Per-instance data for one sprite of an instanced draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpriteInstance {
+    /// Transform of the unit quad.
+    pub model: Matrix4<f32>,
+    /// Texture coordinates of the quad's bottom-left and top-right corners,
+    /// as GL samples them (top of the image at 0).
+    pub uv_min: Point2<f32>,
+    pub uv_max: Point2<f32>,
+    pub tint: [f32; 4],
+}
+
+impl SpriteInstance {
+    pub fn of(sprite: &Sprite) -> Self {
+        let region = &sprite.region;
+        // region uvs are bottom up like mesh uvs, which are flipped on upload
+        Self {
+            model: sprite.model(),
+            uv_min: Point2::new(region.uv_min.x, 1.0 - region.uv_min.y),
+            uv_max: Point2::new(region.uv_max.x, 1.0 - region.uv_max.y),
+            tint: sprite.tint,
+        }
+    }
+}
+
+/// Quad from -0.5 to 0.5 on the x/z plane, with uvs from 0 to 1 used to
+/// interpolate across each instance's uv rectangle.
+pub(crate) fn unit_quad() -> [Vert; 4] {
+    let vert = |x: f32, z: f32, u: f32, v: f32| {
+        Vert::new(
+            Point3::new(x, 0.0, z),
+            Point3::new(1.0, 1.0, 1.0),
+            Point2::new(u, v),
+            Point3::new(0.0, 0.0, 0.0),
+        )
+    };
+    [
+        vert(-0.5, -0.5, 0.0, 0.0),
+        vert(0.5, -0.5, 1.0, 0.0),
+        vert(0.5, 0.5, 1.0, 1.0),
+        vert(-0.5, 0.5, 0.0, 1.0),
+    ]
+}
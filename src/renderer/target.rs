@@ -0,0 +1,183 @@
+use super::texture::{Texture, TextureFormat};
+use crate::image::Image;
+use gl::{self, types::*};
+use std::fmt;
+
+/// Size and attachment formats of a `RenderTarget`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetDesc {
+    pub width: u32,
+    pub height: u32,
+    pub color: TextureFormat,
+    /// `DepthComponent` for a depth buffer, `DepthStencil` for depth and
+    /// stencil, or `None` to draw without a depth test.
+    pub depth: Option<TextureFormat>,
+}
+
+impl TargetDesc {
+    /// An RGBA target with no depth buffer.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            color: TextureFormat::Rgba,
+            depth: None,
+        }
+    }
+    pub fn with_color(mut self, format: TextureFormat) -> Self {
+        self.color = format;
+        self
+    }
+    pub fn with_depth(mut self, format: TextureFormat) -> Self {
+        self.depth = Some(format);
+        self
+    }
+    /// Check the size is non-zero and each attachment has a format it can
+    /// take.
+    pub fn validate(&self) -> Result<(), TargetError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(TargetError::Empty);
+        }
+        if self.color.is_depth() {
+            return Err(TargetError::Format(self.color));
+        }
+        match self.depth {
+            Some(depth) if !depth.is_depth() => Err(TargetError::Format(depth)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TargetError {
+    /// Zero pixels wide or high.
+    Empty,
+    /// A depth format for the colour attachment, or a colour format for the
+    /// depth one.
+    Format(TextureFormat),
+    /// GL rejected the attachments, with the status it gave.
+    Incomplete(GLenum),
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TargetError::Empty => write!(f, "render target has no pixels"),
+            TargetError::Format(format) => write!(f, "{:?} can't be used for that attachment", format),
+            TargetError::Incomplete(status) => write!(f, "framebuffer is incomplete (status {:#x})", status),
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+/// A framebuffer object drawing into textures instead of the screen, so the
+/// result can be sampled by later draws or read back.
+pub struct RenderTarget {
+    fbo: GLuint,
+    pub color: Texture,
+    pub depth: Option<Texture>,
+    desc: TargetDesc,
+}
+
+impl RenderTarget {
+    pub fn new(desc: &TargetDesc) -> Result<RenderTarget, TargetError> {
+        desc.validate()?;
+        let color = Texture::empty(desc.width, desc.height, desc.color);
+        let depth = desc.depth.map(|format| Texture::empty(desc.width, desc.height, format));
+        let mut fbo = 0;
+        unsafe {
+            let mut previous = 0;
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut previous);
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, color.id, 0);
+            if let Some(depth) = &depth {
+                let attachment = match depth.format {
+                    TextureFormat::DepthStencil => gl::DEPTH_STENCIL_ATTACHMENT,
+                    _ => gl::DEPTH_ATTACHMENT,
+                };
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, depth.id, 0);
+            }
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+            let target = RenderTarget {
+                fbo,
+                color,
+                depth,
+                desc: *desc,
+            };
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(TargetError::Incomplete(status));
+            }
+            Ok(target)
+        }
+    }
+    pub fn desc(&self) -> &TargetDesc {
+        &self.desc
+    }
+    pub fn width(&self) -> u32 {
+        self.desc.width
+    }
+    pub fn height(&self) -> u32 {
+        self.desc.height
+    }
+    /// Reallocate the attachments at a new size, e.g. when the window is
+    /// resized. What they held is lost. Zero sizes, as a minimised window
+    /// reports, are ignored.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == (self.desc.width, self.desc.height) {
+            return;
+        }
+        self.desc.width = width;
+        self.desc.height = height;
+        self.color.allocate(width, height);
+        if let Some(depth) = &self.depth {
+            depth.allocate(width, height);
+        }
+    }
+    /// Draw into the target from now on, over its whole area.
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.desc.width as i32, self.desc.height as i32);
+        }
+    }
+    /// Read the colour attachment back, top row first.
+    pub fn read_pixels(&self) -> Image {
+        let (width, height) = (self.desc.width, self.desc.height);
+        let mut pixels = vec![0u8; (width * height * 4) as usize];
+        unsafe {
+            let mut previous = 0;
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut std::ffi::c_void,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous as GLuint);
+        }
+        // GL reads bottom row first
+        let row = (width * 4) as usize;
+        let pixels = pixels.chunks(row).rev().flatten().copied().collect();
+        Image::new(width, height, pixels)
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.color.id);
+            if let Some(depth) = &self.depth {
+                gl::DeleteTextures(1, &depth.id);
+            }
+        }
+    }
+}
@@ -1,7 +1,13 @@
+use crate::image::Image;
 use gl::{self, types::*};
+use nalgebra::{Point2, Vector2};
 use png;
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
+use std::path::Path;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextureFormat {
     DepthComponent,
     DepthStencil,
@@ -10,6 +16,26 @@ pub enum TextureFormat {
     Rgb,
     Rgba,
 }
+
+impl TextureFormat {
+    /// Whether it holds depth, so attaches as a framebuffer's depth buffer
+    /// rather than its colour.
+    pub fn is_depth(&self) -> bool {
+        matches!(self, TextureFormat::DepthComponent | TextureFormat::DepthStencil)
+    }
+    /// GL internal format, pixel format and pixel type of a texture in this
+    /// format.
+    fn gl_formats(&self) -> (GLenum, GLenum, GLenum) {
+        match self {
+            TextureFormat::DepthComponent => (gl::DEPTH_COMPONENT24, gl::DEPTH_COMPONENT, gl::UNSIGNED_INT),
+            TextureFormat::DepthStencil => (gl::DEPTH24_STENCIL8, gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+            TextureFormat::Red => (gl::R8, gl::RED, gl::UNSIGNED_BYTE),
+            TextureFormat::Rg => (gl::RG8, gl::RG, gl::UNSIGNED_BYTE),
+            TextureFormat::Rgb => (gl::RGB8, gl::RGB, gl::UNSIGNED_BYTE),
+            TextureFormat::Rgba => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+        }
+    }
+}
 pub enum BitDepth {
     Byte(u8),
     Short(u16),
@@ -37,9 +63,53 @@ impl Texture {
         let info = reader.next_frame(&mut buf).unwrap();
         // Grab the bytes of the image.
         let bytes = &buf[..info.buffer_size()];
-        // Inspect more details of the last read frame.
-        let in_animation = reader.info().frame_control.is_some();
 
+        Self::upload(reader.info().width, reader.info().height, bytes)
+    }
+    /// Upload an RGBA image, top row first.
+    pub fn from_image(image: &Image) -> Texture {
+        Self::upload(image.width, image.height, &image.pixels)
+    }
+    /// A `width` by `height` texture with undefined contents, for drawing
+    /// into. Sampled nearest and clamped at the edges.
+    pub fn empty(width: u32, height: u32, format: TextureFormat) -> Texture {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+            gl::BindTexture(gl::TEXTURE_2D, id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        }
+        let texture = Self {
+            id,
+            format,
+            bytes: Vec::new(),
+        };
+        texture.allocate(width, height);
+        texture
+    }
+    /// Reallocate the texture at a new size. Its contents become undefined.
+    pub fn allocate(&self, width: u32, height: u32) {
+        let (internal, format, kind) = self.format.gl_formats();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal as i32,
+                width as i32,
+                height as i32,
+                0,
+                format,
+                kind,
+                std::ptr::null(),
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+    }
+    fn upload(width: u32, height: u32, rgba: &[u8]) -> Texture {
         let mut id: GLuint = 0;
 
         unsafe {
@@ -53,19 +123,147 @@ impl Texture {
                 gl::TEXTURE_2D,
                 0,
                 gl::RGBA as i32,
-                reader.info().width as i32,
-                reader.info().height as i32,
+                width as i32,
+                height as i32,
                 0,
                 gl::RGBA,
                 gl::UNSIGNED_BYTE,
-                bytes.as_ptr() as *const std::ffi::c_void,
+                rgba.as_ptr() as *const std::ffi::c_void,
                 );
             gl::GenerateMipmap(gl::TEXTURE_2D);
         }
         Self {
-            id: id,
+            id,
             format: TextureFormat::Rgba,
             bytes: Vec::new(),
         }
     }
 }
+
+/// A named rectangle of an `Atlas`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Region {
+    /// Pixel rectangle `[x, y, width, height]` from the image's top left.
+    pub rect: [u32; 4],
+    /// Bottom-left and top-right texture coordinates, bottom up like the
+    /// UVs of mesh vertices.
+    pub uv_min: Point2<f32>,
+    pub uv_max: Point2<f32>,
+}
+
+impl Region {
+    /// Texture coordinates for `rect` in an image `width` by `height`. Each
+    /// edge is pulled in by half a texel so that filtering and rounding never
+    /// sample the neighbouring sprite.
+    pub fn new(rect: [u32; 4], width: u32, height: u32) -> Self {
+        let [x, y, w, h] = rect.map(|v| v as f32);
+        let (width, height) = (width as f32, height as f32);
+        Self {
+            rect,
+            uv_min: Point2::new((x + 0.5) / width, 1.0 - (y + h - 0.5) / height),
+            uv_max: Point2::new((x + w - 0.5) / width, 1.0 - (y + 0.5) / height),
+        }
+    }
+    /// Size in pixels.
+    pub fn size(&self) -> Vector2<f32> {
+        Vector2::new(self.rect[2] as f32, self.rect[3] as f32)
+    }
+    /// Corner UVs in `Quad` vertex order: bottom left, bottom right, top
+    /// right, top left.
+    pub fn uvs(&self) -> [Point2<f32>; 4] {
+        let (min, max) = (self.uv_min, self.uv_max);
+        [
+            min,
+            Point2::new(max.x, min.y),
+            max,
+            Point2::new(min.x, max.y),
+        ]
+    }
+    /// Like `uvs`, but upside down.
+    pub fn uvs_flipped(&self) -> [Point2<f32>; 4] {
+        let [bl, br, tr, tl] = self.uvs();
+        [tl, tr, br, bl]
+    }
+}
+
+/// Names for the sprites packed into one texture.
+///
+/// The regions come from a sidecar file next to the image with the same name
+/// and a `.toml` extension, mapping each name to `[x, y, width, height]` in
+/// pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atlas {
+    pub width: u32,
+    pub height: u32,
+    regions: HashMap<String, Region>,
+}
+
+impl Atlas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            regions: HashMap::new(),
+        }
+    }
+    /// Load the sidecar for the image at `image`. Only the image's header is
+    /// read, so no GL context is needed.
+    pub fn from_path(image: &Path) -> Result<Self, AtlasError> {
+        let file = File::open(image).map_err(AtlasError::Io)?;
+        let reader = png::Decoder::new(file).read_info().map_err(AtlasError::Image)?;
+        let (width, height) = (reader.info().width, reader.info().height);
+        let sidecar = std::fs::read_to_string(image.with_extension("toml")).map_err(AtlasError::Io)?;
+        Self::parse(width, height, &sidecar)
+    }
+    /// Build an atlas for an image `width` by `height` from sidecar text.
+    pub fn parse(width: u32, height: u32, text: &str) -> Result<Self, AtlasError> {
+        let rects: HashMap<String, [u32; 4]> = toml::from_str(text).map_err(AtlasError::Parse)?;
+        let mut atlas = Self::new(width, height);
+        for (name, rect) in rects {
+            atlas.insert(name, rect)?;
+        }
+        Ok(atlas)
+    }
+    /// Name the pixel rectangle `rect`, replacing any region of that name.
+    pub fn insert(&mut self, name: impl Into<String>, rect: [u32; 4]) -> Result<(), AtlasError> {
+        let name = name.into();
+        let [x, y, w, h] = rect;
+        // checked, as a rect near u32::MAX would wrap back inside the image
+        let past = |start: u32, len: u32, end: u32| start.checked_add(len).is_none_or(|e| e > end);
+        if w == 0 || h == 0 || past(x, w, self.width) || past(y, h, self.height) {
+            return Err(AtlasError::OutOfBounds(name, rect));
+        }
+        self.regions.insert(name, Region::new(rect, self.width, self.height));
+        Ok(())
+    }
+    pub fn region(&self, name: &str) -> Option<&Region> {
+        self.regions.get(name)
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(String::as_str)
+    }
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(std::io::Error),
+    Image(png::DecodingError),
+    Parse(toml::de::Error),
+    /// A region that is empty or runs off the edge of the image.
+    OutOfBounds(String, [u32; 4]),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AtlasError::Io(e) => write!(f, "{}", e),
+            AtlasError::Image(e) => write!(f, "{}", e),
+            AtlasError::Parse(e) => write!(f, "{}", e),
+            AtlasError::OutOfBounds(name, rect) => {
+                write!(f, "region `{}` {:?} is empty or outside the image", name, rect)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
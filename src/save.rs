@@ -0,0 +1,289 @@
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the save format written by this build. Files from newer builds
+/// are refused rather than half-read.
+pub const VERSION: u32 = 1;
+
+/// A format from before the first release that `parse` refuses, kept so the
+/// tests can run a table through `migrate` before there is a second real
+/// version. It called the table `scores`.
+#[doc(hidden)]
+pub const TEST_VERSION: u32 = 0;
+
+/// How many scores the high-score table keeps.
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    /// Day the score was set, as `YYYY-MM-DD` in UTC.
+    pub date: String,
+    /// Seed of the pipe course the score was set on.
+    #[serde(with = "hex_seed")]
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub fullscreen: bool,
+    /// Play the same course every round instead of a new one.
+    #[serde(with = "hex_seed_opt", skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// Everything kept between runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveData {
+    pub version: u32,
+    #[serde(default)]
+    pub high_scores: Vec<HighScore>,
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+impl SaveData {
+    pub fn new() -> Self {
+        Self {
+            version: VERSION,
+            high_scores: Vec::new(),
+            settings: Settings::default(),
+        }
+    }
+    /// Add `score` to the table if it makes it in, returning its rank from 0.
+    /// Ties go below the scores already there.
+    pub fn record(&mut self, score: HighScore) -> Option<usize> {
+        let rank = self.high_scores.partition_point(|s| s.score >= score.score);
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.high_scores.insert(rank, score);
+        self.high_scores.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+    pub fn best(&self) -> Option<&HighScore> {
+        self.high_scores.first()
+    }
+    /// Parse a save file written by this build or an older one.
+    pub fn parse(text: &str) -> Result<Self, SaveError> {
+        let mut table: toml::Table = text.parse().map_err(SaveError::Parse)?;
+        let version = match table.get("version") {
+            Some(toml::Value::Integer(v)) => u32::try_from(*v).map_err(|_| SaveError::MissingVersion)?,
+            Some(_) | None => return Err(SaveError::MissingVersion),
+        };
+        if version == TEST_VERSION || version > VERSION {
+            return Err(SaveError::Version(version));
+        }
+        migrate(&mut table, version);
+        let mut data: SaveData = toml::Value::Table(table)
+            .try_into()
+            .map_err(SaveError::Parse)?;
+        data.high_scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+        data.high_scores.truncate(MAX_HIGH_SCORES);
+        Ok(data)
+    }
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("save data always serializes")
+    }
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Bring a table written by format `from` up to `VERSION`, one version at a
+/// time.
+pub fn migrate(table: &mut toml::Table, from: u32) {
+    let mut version = from;
+    while version < VERSION {
+        match version {
+            TEST_VERSION => {
+                if let Some(scores) = table.remove("scores") {
+                    table.insert("high_scores".to_string(), scores);
+                }
+            }
+            _ => unreachable!("no migration from save version {}", version),
+        }
+        version += 1;
+    }
+    table.insert("version".to_string(), toml::Value::Integer(VERSION as i64));
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// No `version`, or one that isn't a whole number.
+    MissingVersion,
+    /// A version this build doesn't know how to read.
+    Version(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "{}", e),
+            SaveError::Parse(e) => write!(f, "{}", e),
+            SaveError::MissingVersion => write!(f, "missing save version"),
+            SaveError::Version(v) => write!(f, "unsupported save version {} (newest known is {})", v, VERSION),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+/// Resource pairing the save data with the file it lives in.
+#[derive(Debug, Clone)]
+pub struct SaveFile {
+    path: PathBuf,
+    pub data: SaveData,
+}
+
+impl SaveFile {
+    /// An empty save that will be written to `path`, ignoring anything there.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            data: SaveData::new(),
+        }
+    }
+    /// Load the save at `path`, falling back to an empty save if it is missing.
+    /// A file that can't be read is moved aside to `<name>.corrupt` so it
+    /// isn't overwritten by the next save.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let data = match fs::read_to_string(&path) {
+            Ok(text) => SaveData::parse(&text).unwrap_or_else(|e| {
+                let backup = path.with_extension("toml.corrupt");
+                eprintln!(
+                    "{} could not read save {}: {}; moving it to {}",
+                    "warning:".bold().red(),
+                    path.display(),
+                    e,
+                    backup.display()
+                );
+                let _ = fs::rename(&path, &backup);
+                SaveData::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SaveData::new(),
+            Err(e) => {
+                eprintln!("{} could not open save {}: {}", "warning:".bold().red(), path.display(), e);
+                SaveData::new()
+            }
+        };
+        Self { path, data }
+    }
+    /// Open `save.toml` in the game's XDG data directory, or one in the
+    /// working directory if there is no home to find it in.
+    pub fn open_default() -> Self {
+        let dir = data_dir().unwrap_or_else(|| PathBuf::from("."));
+        Self::open(dir.join("save.toml"))
+    }
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    /// Write the save out atomically: to a temporary file next to it which is
+    /// synced and then renamed over the old one, so a crash mid-write leaves
+    /// either the old save or the new one.
+    pub fn save(&self) -> Result<(), SaveError> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = self.path.with_extension("toml.tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(self.data.to_toml().as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+    /// Record a finished round and write the save if it made the table.
+    pub fn record(&mut self, score: u32, seed: u64) -> Option<usize> {
+        let rank = self.data.record(HighScore {
+            score,
+            date: today(),
+            seed,
+        })?;
+        if let Err(e) = self.save() {
+            eprintln!("{} could not write save {}: {}", "warning:".bold().red(), self.path.display(), e);
+        }
+        Some(rank)
+    }
+}
+
+/// `$XDG_DATA_HOME/flappy`, or `~/.local/share/flappy` if that isn't set.
+pub fn data_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_DATA_HOME").filter(|d| Path::new(d).is_absolute()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(base.join("flappy"))
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Calendar date of a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Seeds are written as hex strings, since TOML integers can't hold a full
+/// `u64`.
+mod hex_seed {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn format(seed: u64) -> String {
+        format!("{:016x}", seed)
+    }
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(*seed))
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        u64::from_str_radix(&text, 16).map_err(serde::de::Error::custom)
+    }
+}
+
+mod hex_seed_opt {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(seed: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match seed {
+            Some(seed) => super::hex_seed::serialize(seed, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+        let text = Option::<String>::deserialize(deserializer)?;
+        text.map(|t| u64::from_str_radix(&t, 16).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
@@ -0,0 +1,302 @@
+use crate::scene::Scene;
+use crate::state::{self, GameState, State};
+use crate::time::Time;
+use crate::timestep::{self, FixedTimestep};
+use std::collections::HashMap;
+
+/// Phases of a frame, run in declaration order by `Schedule::run`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    Input,
+    FixedUpdate,
+    Update,
+    LateUpdate,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::Input,
+        Stage::FixedUpdate,
+        Stage::Update,
+        Stage::LateUpdate,
+        Stage::Render,
+    ];
+}
+
+pub type System = Box<dyn FnMut(&mut Scene)>;
+
+/// Predicate deciding whether a system runs this time round.
+pub type RunCondition = Box<dyn Fn(&Scene) -> bool>;
+
+struct SystemEntry {
+    name: &'static str,
+    system: System,
+    after: Vec<&'static str>,
+    before: Vec<&'static str>,
+    /// All must hold for the system to run.
+    conditions: Vec<RunCondition>,
+}
+
+impl SystemEntry {
+    fn should_run(&self, scene: &Scene) -> bool {
+        self.conditions.iter().all(|condition| condition(scene))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Transition {
+    Enter,
+    Exit,
+}
+
+#[derive(Default)]
+struct StageSystems {
+    systems: Vec<SystemEntry>,
+    /// Execution order, recomputed whenever a system or constraint is added.
+    order: Option<Vec<usize>>,
+}
+
+impl StageSystems {
+    fn ensure_sorted(&mut self) {
+        if self.order.is_none() {
+            self.order = Some(self.sort());
+        }
+    }
+    /// Topologically sort the stage's systems by their `after`/`before`
+    /// constraints. Systems with no constraint between them keep the order
+    /// they were added in.
+    fn sort(&self) -> Vec<usize> {
+        let index_of = |name: &str, from: &str| {
+            self.systems
+                .iter()
+                .position(|s| s.name == name)
+                .unwrap_or_else(|| panic!("system `{}` is ordered against unknown system `{}`", from, name))
+        };
+        let mut edges = vec![Vec::new(); self.systems.len()];
+        let mut incoming = vec![0usize; self.systems.len()];
+        for (i, entry) in self.systems.iter().enumerate() {
+            for name in &entry.after {
+                edges[index_of(name, entry.name)].push(i);
+                incoming[i] += 1;
+            }
+            for name in &entry.before {
+                edges[i].push(index_of(name, entry.name));
+                incoming[index_of(name, entry.name)] += 1;
+            }
+        }
+
+        let mut order = Vec::with_capacity(self.systems.len());
+        let mut done = vec![false; self.systems.len()];
+        while order.len() < self.systems.len() {
+            let next = (0..self.systems.len())
+                .find(|&i| !done[i] && incoming[i] == 0)
+                .unwrap_or_else(|| {
+                    let stuck: Vec<&str> = (0..self.systems.len())
+                        .filter(|&i| !done[i])
+                        .map(|i| self.systems[i].name)
+                        .collect();
+                    panic!("system ordering cycle between {:?}", stuck)
+                });
+            done[next] = true;
+            for &to in &edges[next] {
+                incoming[to] -= 1;
+            }
+            order.push(next);
+        }
+        order
+    }
+}
+
+/// Collection of systems grouped into stages.
+///
+/// A system is any `FnMut(&mut Scene)`; shared state such as the keymap or
+/// frame time lives in the scene as resources, so systems can be run against a
+/// bare `Scene` in tests.
+///
+/// Systems can also be hooked onto entering or leaving a `GameState`; see
+/// `State` for when transitions happen.
+#[derive(Default)]
+pub struct Schedule {
+    stages: HashMap<Stage, StageSystems>,
+    hooks: HashMap<(GameState, Transition), Vec<(&'static str, System)>>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self {
+            stages: HashMap::new(),
+            hooks: HashMap::new(),
+        }
+    }
+    /// Register `system` under `name` in `stage`. The returned handle can add
+    /// ordering constraints against other systems in the same stage.
+    pub fn add_system(
+        &mut self,
+        stage: Stage,
+        name: &'static str,
+        system: impl FnMut(&mut Scene) + 'static,
+    ) -> SystemConfig<'_> {
+        let systems = self.stages.entry(stage).or_default();
+        if systems.systems.iter().any(|s| s.name == name) {
+            panic!("system `{}` registered twice in {:?}", name, stage);
+        }
+        systems.systems.push(SystemEntry {
+            name,
+            system: Box::new(system),
+            after: Vec::new(),
+            before: Vec::new(),
+            conditions: Vec::new(),
+        });
+        systems.order = None;
+        SystemConfig {
+            index: systems.systems.len() - 1,
+            stage: systems,
+        }
+    }
+    /// Run `system` whenever `state` is entered. Hooks of one state run in the
+    /// order they were added.
+    pub fn on_enter(&mut self, state: GameState, name: &'static str, system: impl FnMut(&mut Scene) + 'static) {
+        self.add_hook(state, Transition::Enter, name, system);
+    }
+    /// Run `system` whenever `state` is left.
+    pub fn on_exit(&mut self, state: GameState, name: &'static str, system: impl FnMut(&mut Scene) + 'static) {
+        self.add_hook(state, Transition::Exit, name, system);
+    }
+    fn add_hook(
+        &mut self,
+        state: GameState,
+        transition: Transition,
+        name: &'static str,
+        system: impl FnMut(&mut Scene) + 'static,
+    ) {
+        let hooks = self.hooks.entry((state, transition)).or_default();
+        if hooks.iter().any(|(n, _)| *n == name) {
+            panic!("hook `{}` registered twice on {:?} {:?}", name, transition, state);
+        }
+        hooks.push((name, Box::new(system)));
+    }
+    fn run_hooks(&mut self, state: GameState, transition: Transition, scene: &mut Scene) {
+        if let Some(hooks) = self.hooks.get_mut(&(state, transition)) {
+            for (_, system) in hooks {
+                system(scene);
+            }
+        }
+    }
+    /// Make any transition requested on the scene's `State`, running the exit
+    /// hooks of the old state and the enter hooks of the new one. Hooks may
+    /// request a further transition, which is made straight away.
+    pub fn apply_transitions(&mut self, scene: &mut Scene) {
+        while let Some((from, to)) = scene.resource_mut::<State>().and_then(State::take_transition) {
+            if let Some(from) = from {
+                self.run_hooks(from, Transition::Exit, scene);
+            }
+            if let Some(state) = scene.resource_mut::<State>() {
+                state.enter(to);
+            }
+            self.run_hooks(to, Transition::Enter, scene);
+        }
+    }
+    /// Names of the systems in `stage`, in the order they will run.
+    pub fn system_order(&mut self, stage: Stage) -> Vec<&'static str> {
+        let Some(systems) = self.stages.get_mut(&stage) else {
+            return Vec::new();
+        };
+        systems.ensure_sorted();
+        let order = systems.order.as_ref().unwrap();
+        order.iter().map(|&i| systems.systems[i].name).collect()
+    }
+    pub fn run_stage(&mut self, stage: Stage, scene: &mut Scene) {
+        let Some(systems) = self.stages.get_mut(&stage) else {
+            return;
+        };
+        systems.ensure_sorted();
+        let order = systems.order.as_ref().unwrap();
+        for &i in order {
+            let entry = &mut systems.systems[i];
+            if entry.should_run(scene) {
+                (entry.system)(scene);
+            }
+        }
+    }
+    /// Run every stage in order.
+    ///
+    /// If the scene has a `FixedTimestep` resource, `Stage::FixedUpdate` runs
+    /// as many whole ticks as the frame's `Time::delta` pays for, with `Time`
+    /// set to the tick length for the duration; otherwise it runs once.
+    ///
+    /// State transitions are applied before the first stage and after every
+    /// stage and fixed tick.
+    pub fn run(&mut self, scene: &mut Scene) {
+        self.apply_transitions(scene);
+        for stage in Stage::ALL {
+            if stage == Stage::FixedUpdate && scene.has_resource::<FixedTimestep>() {
+                self.run_fixed(scene);
+            } else {
+                self.run_stage(stage, scene);
+            }
+            self.apply_transitions(scene);
+        }
+    }
+    fn run_fixed(&mut self, scene: &mut Scene) {
+        let frame_time = scene.resource::<Time>().copied().unwrap_or_default();
+        let mut fixed = scene.remove_resource::<FixedTimestep>().unwrap();
+        fixed.accumulate(frame_time.delta);
+
+        let mut steps = 0;
+        while fixed.expend() {
+            timestep::snapshot_transforms(scene);
+            scene.insert_resource(Time {
+                delta: fixed.step,
+                elapsed: fixed.elapsed(),
+            });
+            scene.insert_resource(fixed);
+            self.run_stage(Stage::FixedUpdate, scene);
+            self.apply_transitions(scene);
+            fixed = scene.remove_resource::<FixedTimestep>().unwrap();
+
+            steps += 1;
+            if steps >= fixed.max_steps {
+                fixed.drop_backlog();
+                break;
+            }
+        }
+        fixed.update_alpha();
+        scene.insert_resource(fixed);
+        scene.insert_resource(frame_time);
+    }
+}
+
+pub struct SystemConfig<'a> {
+    stage: &'a mut StageSystems,
+    index: usize,
+}
+
+impl SystemConfig<'_> {
+    /// Run this system after `name`.
+    pub fn after(self, name: &'static str) -> Self {
+        self.stage.systems[self.index].after.push(name);
+        self.stage.order = None;
+        self
+    }
+    /// Run this system before `name`.
+    pub fn before(self, name: &'static str) -> Self {
+        self.stage.systems[self.index].before.push(name);
+        self.stage.order = None;
+        self
+    }
+    /// Only run this system while `condition` holds. Several conditions must
+    /// all hold.
+    pub fn run_if(self, condition: impl Fn(&Scene) -> bool + 'static) -> Self {
+        self.stage.systems[self.index].conditions.push(Box::new(condition));
+        self
+    }
+    /// Only run this system while the scene's `State` is `state`.
+    pub fn in_state(self, state: GameState) -> Self {
+        self.run_if(state::in_state(state))
+    }
+    /// Only run this system while the scene's `State` is one of `states`.
+    pub fn in_states(self, states: &'static [GameState]) -> Self {
+        self.run_if(state::in_any_state(states))
+    }
+}
@@ -0,0 +1,118 @@
+use crate::camera::Camera;
+use crate::components::Mesh;
+use crate::entity::Entity;
+use crate::game::{self, Pipe, Player, PIPE_WIDTH};
+use crate::renderer::layer::{Layer, RenderLayer};
+use crate::scene::Scene;
+use nalgebra::{Point2, Translation3};
+
+/// Pipe pairs passed this round.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Score {
+    pub value: u32,
+}
+
+impl Score {
+    pub fn new() -> Self {
+        Self { value: 0 }
+    }
+}
+
+/// Marker for pipe pairs that have already been counted.
+#[derive(Debug, Default)]
+pub struct Scored;
+
+/// Marker for the mesh showing the score, with the value it was built for.
+#[derive(Debug, Default)]
+pub struct ScoreHud {
+    pub value: u32,
+}
+
+/// Name of the large glyph for `digit` in the sprite atlas.
+fn digit_sprite(digit: u8) -> String {
+    format!("digit_{}", digit)
+}
+
+/// Space between glyphs.
+const DIGIT_SPACING: f32 = 2.0;
+/// Space between the top of the screen and the top of the score.
+const HUD_MARGIN: f32 = 24.0;
+
+/// Count every pipe pair whose trailing edge the bird has passed. Does nothing
+/// without a `Score` resource.
+pub fn score_pipes(scene: &mut Scene) {
+    let Some(bird_x) = scene
+        .query::<&Mesh>()
+        .with::<Player>()
+        .next()
+        .map(|mesh| mesh.translation.x)
+    else {
+        return;
+    };
+    let passed: Vec<Entity> = scene
+        .query::<(Entity, &Mesh)>()
+        .with::<Pipe>()
+        .without::<Scored>()
+        .filter(|(_, mesh)| mesh.translation.x + PIPE_WIDTH < bird_x)
+        .map(|(entity, _)| entity)
+        .collect();
+    let Some(score) = scene.resource_mut::<Score>() else {
+        return;
+    };
+    score.value += passed.len() as u32;
+    for pipe in passed {
+        pipe.insert(scene, Scored);
+    }
+}
+
+/// `value` laid out in digit glyphs, centred on the origin.
+pub fn digits_mesh(value: u32) -> Mesh {
+    let glyphs: Vec<_> = value
+        .to_string()
+        .bytes()
+        .map(|b| game::sprite(&digit_sprite(b - b'0')))
+        .collect();
+    let width: f32 = glyphs.iter().map(|g| g.size().x).sum::<f32>() + DIGIT_SPACING * (glyphs.len() - 1) as f32;
+    let mut left = -width / 2.0;
+    game::sprites_mesh(glyphs.into_iter().map(|glyph| {
+        let center = Point2::new(left + glyph.size().x / 2.0, 0.0);
+        left += glyph.size().x + DIGIT_SPACING;
+        (glyph, center)
+    }))
+}
+
+/// Keep the score centred at the top of the visible area, spawning the HUD
+/// if it isn't shown yet. The mesh is only rebuilt when the score changes.
+pub fn update_score_hud(scene: &mut Scene) {
+    let Some(value) = scene.resource::<Score>().map(|s| s.value) else {
+        return;
+    };
+    let (_, half_height) = scene.resource::<Camera>().map_or((0.0, 128.0), Camera::half_extents);
+    let mesh_height = game::sprite(&digit_sprite(0)).size().y;
+    let translation = Translation3::new(0.0, 0.0, half_height - HUD_MARGIN - mesh_height / 2.0);
+
+    match scene.query::<(&mut Mesh, &mut ScoreHud)>().next() {
+        Some((mesh, hud)) => {
+            if hud.value != value {
+                *mesh = digits_mesh(value);
+                hud.value = value;
+            }
+            mesh.translation = translation;
+        }
+        None => {
+            let mut mesh = digits_mesh(value);
+            mesh.translation = translation;
+            let hud = scene.spawn();
+            hud.add_mesh(scene, mesh);
+            hud.insert(scene, ScoreHud { value });
+            hud.insert(scene, RenderLayer::new(Layer::Hud));
+        }
+    }
+}
+
+pub fn hide_score_hud(scene: &mut Scene) {
+    let huds: Vec<Entity> = scene.query::<Entity>().with::<ScoreHud>().collect();
+    for hud in huds {
+        scene.despawn(hud);
+    }
+}
@@ -23,6 +23,24 @@ impl Shader {
         );
         }
     }
+    pub fn set_vec2(&self, name: &str, value: [f32; 2]) {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            gl::Uniform2fv(gl::GetUniformLocation(self.id, name.as_ptr()), 1, value.as_ptr());
+        }
+    }
+    pub fn set_vec3(&self, name: &str, value: [f32; 3]) {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            gl::Uniform3fv(gl::GetUniformLocation(self.id, name.as_ptr()), 1, value.as_ptr());
+        }
+    }
+    pub fn set_vec4(&self, name: &str, value: [f32; 4]) {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            gl::Uniform4fv(gl::GetUniformLocation(self.id, name.as_ptr()), 1, value.as_ptr());
+        }
+    }
     pub fn set_mat4(&self, name: &str, mat: &nalgebra::base::Matrix4<f32>) {
         let name = CString::new(name).unwrap();
         unsafe {
@@ -30,13 +48,16 @@ impl Shader {
         }
     }
     pub fn set_tex(&self, name: &str, tex: &Texture) {
+        self.set_tex_unit(name, tex, 0);
+    }
+    /// Bind `tex` to texture unit `unit` and point the sampler `name` at it.
+    pub fn set_tex_unit(&self, name: &str, tex: &Texture, unit: u32) {
         let name = CString::new(name).unwrap();
         unsafe {
-            //gl::Uniform1i(gl::GetUniformLocation(self.id, name.as_ptr()), tex.id as i32);
-            gl::ActiveTexture(gl::TEXTURE0);
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
             gl::BindTexture(gl::TEXTURE_2D, tex.id);
-            gl::Uniform1i(gl::GetUniformLocation(self.id, name.as_ptr()), 0 as i32);
-            //gl::UniformMatrix4fv(gl::GetUniformLocation(self.id, name.as_ptr()), 1, gl::FALSE, mat.as_ptr());
+            gl::Uniform1i(gl::GetUniformLocation(self.id, name.as_ptr()), unit as i32);
+            gl::ActiveTexture(gl::TEXTURE0);
         }
     }
     pub fn new(vs_path: &std::path::Path, fs_path: &std::path::Path) -> Shader {
@@ -77,7 +98,7 @@ impl Shader {
         }
         Self::print_shader_link(id);
 
-        Shader { id: id }
+        Shader { id }
     }
 
     fn print_shader_compilation(shader: u32, path: &std::path::Path) {
@@ -109,7 +130,7 @@ impl Shader {
                     print!("{0} ", w.normal());
                 }
             });
-            println!("");
+            println!();
         }
     }
     fn print_shader_link(program: u32) {
@@ -140,7 +161,7 @@ impl Shader {
                     print!("{0} ", w.normal());
                 }
             });
-            println!("");
+            println!();
         }
     }
 }
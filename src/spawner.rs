@@ -0,0 +1,119 @@
+use crate::collision::{Collider, ShapeKind};
+use crate::components::Mesh;
+use crate::entity::Entity;
+use crate::game::{self, Pipe, PIPE_WIDTH};
+use crate::renderer::layer::{Layer, RenderLayer};
+use crate::rng::Rng;
+use crate::scene::Scene;
+use crate::time::Time;
+use nalgebra::{Translation3, Vector3};
+
+/// Resource that lays out the pipe course.
+///
+/// Pipe pairs enter at `spawn_x` every `spacing` units of scrolling, with the
+/// centre of their gap picked uniformly from `gap_center` by a seeded `Rng`, so
+/// the same seed always produces the same course. Pairs whose right edge has
+/// scrolled past `despawn_x` are despawned.
+#[derive(Debug, Clone)]
+pub struct PipeSpawner {
+    /// Scroll speed of the pipes, in units per second.
+    pub speed: f32,
+    /// Horizontal distance between consecutive pipe pairs.
+    pub spacing: f32,
+    /// Height of the opening between the top and bottom pipe.
+    pub gap: f32,
+    /// Lowest and highest z the centre of a gap may be placed at.
+    pub gap_center: (f32, f32),
+    pub spawn_x: f32,
+    pub despawn_x: f32,
+    seed: u64,
+    rng: Rng,
+    /// Distance scrolled since the last pair was spawned.
+    travelled: f32,
+}
+
+impl PipeSpawner {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            speed: 48.0,
+            spacing: 160.0,
+            gap: 80.0,
+            gap_center: (-60.0, 60.0),
+            spawn_x: 420.0,
+            despawn_x: -450.0,
+            seed,
+            rng: Rng::new(seed),
+            // spawn the first pair straight away
+            travelled: 160.0,
+        }
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Start the course over from its seed.
+    pub fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+        self.travelled = self.spacing;
+    }
+    /// Start a different course from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.reset();
+    }
+    /// Pick the gap centre for the next pair.
+    pub fn next_gap_center(&mut self) -> f32 {
+        let (min, max) = self.gap_center;
+        self.rng.range(min, max)
+    }
+    /// Spawn one pipe pair at `x` with its gap centred on `gap_center`.
+    pub fn spawn_pair(&self, scene: &mut Scene, x: f32, gap_center: f32) -> Entity {
+        let mut mesh = game::pipe_pair_mesh(self.gap);
+        mesh.translation = Translation3::new(x, 0.0, gap_center);
+        let collider = Collider::from_mesh_quads(&mesh, ShapeKind::Aabb);
+
+        let pipes = scene.spawn();
+        pipes.add_mesh(scene, mesh);
+        pipes.add_velocity(scene, Vector3::new(-self.speed, 0.0, 0.0));
+        pipes.insert(scene, collider);
+        pipes.insert(scene, Pipe);
+        pipes.insert(scene, RenderLayer::new(Layer::Pipes));
+        pipes
+    }
+}
+
+impl Default for PipeSpawner {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Spawn pipe pairs as the course scrolls and despawn the ones that have left
+/// the screen. Does nothing without a `PipeSpawner` resource.
+pub fn spawn_pipes(scene: &mut Scene) {
+    if !scene.has_resource::<PipeSpawner>() {
+        return;
+    }
+    let dt = scene.resource::<Time>().map_or(0.0, Time::delta_secs);
+    scene.resource_scope(|scene, spawner: &mut PipeSpawner| {
+        spawner.travelled += spawner.speed * dt;
+        // a spacing of zero or less would spawn pairs forever
+        while spawner.spacing > 0.0 && spawner.travelled >= spawner.spacing {
+            spawner.travelled -= spawner.spacing;
+            // the pair would already have scrolled this far had it spawned on time
+            let x = spawner.spawn_x - spawner.travelled;
+            let gap_center = spawner.next_gap_center();
+            spawner.spawn_pair(scene, x, gap_center);
+        }
+
+        let gone: Vec<Entity> = scene
+            .query::<(Entity, &Mesh)>()
+            .with::<Pipe>()
+            .filter(|(_, mesh)| mesh.translation.x + PIPE_WIDTH < spawner.despawn_x)
+            .map(|(entity, _)| entity)
+            .collect();
+        for entity in gone {
+            scene.despawn(entity);
+        }
+    });
+}
+
@@ -0,0 +1,98 @@
+use crate::scene::Scene;
+
+/// Top-level phases of a round of the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    /// Title screen, waiting for the first tap.
+    #[default]
+    Title,
+    /// Bird hovering at the start line with the tap prompt shown.
+    GetReady,
+    Playing,
+    /// Bird has hit something and is falling to the ground.
+    Dying,
+    /// Round over, waiting for a tap to restart.
+    GameOver,
+}
+
+/// Resource holding the current `GameState` and any transition requested by a
+/// system this frame.
+///
+/// Transitions are applied by `Schedule::run` between stages (and between
+/// fixed ticks), which runs the exit hooks of the old state and then the enter
+/// hooks of the new one.
+#[derive(Debug, Clone, Copy)]
+pub struct State {
+    current: GameState,
+    next: Option<GameState>,
+    /// Whether the enter hooks of the initial state have run yet.
+    entered: bool,
+}
+
+impl State {
+    pub fn new(initial: GameState) -> Self {
+        Self {
+            current: initial,
+            next: None,
+            entered: false,
+        }
+    }
+    pub fn current(&self) -> GameState {
+        self.current
+    }
+    /// Request a transition to `next`. Requesting the current state does
+    /// nothing; a later request in the same frame replaces an earlier one.
+    pub fn set(&mut self, next: GameState) {
+        self.next = Some(next);
+    }
+    pub fn pending(&self) -> Option<GameState> {
+        self.next
+    }
+    /// The transition to make next, as `(state left, state entered)`. The
+    /// first call enters the initial state without leaving any.
+    pub(crate) fn take_transition(&mut self) -> Option<(Option<GameState>, GameState)> {
+        if !self.entered {
+            self.entered = true;
+            return Some((None, self.current));
+        }
+        match self.next.take() {
+            Some(next) if next != self.current => Some((Some(self.current), next)),
+            _ => None,
+        }
+    }
+    pub(crate) fn enter(&mut self, state: GameState) {
+        self.current = state;
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new(GameState::default())
+    }
+}
+
+/// Run condition that holds while the scene's `State` is `state`.
+pub fn in_state(state: GameState) -> impl Fn(&Scene) -> bool {
+    move |scene| scene.resource::<State>().is_some_and(|s| s.current() == state)
+}
+
+/// Run condition that holds while the scene's `State` is any of `states`.
+pub fn in_any_state(states: &'static [GameState]) -> impl Fn(&Scene) -> bool {
+    move |scene| {
+        scene
+            .resource::<State>()
+            .is_some_and(|s| states.contains(&s.current()))
+    }
+}
+
+/// Request a transition on the scene's `State`, if it has one.
+pub fn set_state(scene: &mut Scene, next: GameState) {
+    if let Some(state) = scene.resource_mut::<State>() {
+        state.set(next);
+    }
+}
+
+/// The scene's current state, if it has a `State` resource.
+pub fn current_state(scene: &Scene) -> Option<GameState> {
+    scene.resource::<State>().map(State::current)
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+/// Frame timing, stored in the scene as a resource so systems can read it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Time {
+    /// Time covered by the current update.
+    pub delta: Duration,
+    /// Total simulated time so far.
+    pub elapsed: Duration,
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+        }
+    }
+    /// Advance the clock by `delta`.
+    pub fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+    }
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+}
@@ -0,0 +1,148 @@
+use crate::components::Mesh;
+use crate::entity::Entity;
+use crate::scene::Scene;
+use nalgebra::{Rotation3, Scale3, Translation3};
+use std::fmt;
+use std::time::Duration;
+
+/// Accumulator driving `Stage::FixedUpdate` at a constant tick rate.
+///
+/// Each frame's real time is added to the accumulator and spent in whole ticks,
+/// so the simulation advances identically whatever the display rate is. What is
+/// left over becomes `alpha`, the fraction of a tick the renderer should
+/// interpolate towards the current state.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep {
+    pub step: Duration,
+    /// Most ticks run in one frame; after a long stall the remaining backlog is
+    /// dropped instead of freezing the game catching up.
+    pub max_steps: u32,
+    accumulator: Duration,
+    elapsed: Duration,
+    alpha: f32,
+}
+
+impl FixedTimestep {
+    /// A timestep running `tick_rate` ticks a second.
+    pub fn new(tick_rate: u32) -> Result<Self, TimestepError> {
+        Ok(Self::with_step(step_of(tick_rate)?))
+    }
+    fn with_step(step: Duration) -> Self {
+        Self {
+            step,
+            max_steps: 8,
+            accumulator: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            alpha: 0.0,
+        }
+    }
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+    /// Change the tick rate, keeping the time banked and simulated so far.
+    pub fn set_tick_rate(&mut self, tick_rate: u32) -> Result<(), TimestepError> {
+        self.step = step_of(tick_rate)?;
+        Ok(())
+    }
+    pub fn accumulate(&mut self, frame: Duration) {
+        self.accumulator += frame;
+    }
+    /// Take one tick out of the accumulator if there is enough time banked.
+    pub fn expend(&mut self) -> bool {
+        if self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            self.elapsed += self.step;
+            true
+        } else {
+            false
+        }
+    }
+    /// Forget any time that could not be simulated this frame.
+    pub fn drop_backlog(&mut self) {
+        let remainder = self.accumulator.as_nanos() % self.step.as_nanos();
+        self.accumulator = Duration::from_nanos(remainder as u64);
+    }
+    pub fn update_alpha(&mut self) {
+        self.alpha = self.accumulator.as_secs_f32() / self.step.as_secs_f32();
+    }
+    /// How far between the previous and current tick the frame being drawn is.
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+    /// Simulated time, in whole ticks.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::with_step(Duration::from_secs(1) / 60)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestepError {
+    /// A tick rate of zero, which would never advance.
+    ZeroRate,
+}
+
+impl fmt::Display for TimestepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimestepError::ZeroRate => write!(f, "tick rate must be at least one tick a second"),
+        }
+    }
+}
+
+impl std::error::Error for TimestepError {}
+
+fn step_of(tick_rate: u32) -> Result<Duration, TimestepError> {
+    if tick_rate == 0 {
+        return Err(TimestepError::ZeroRate);
+    }
+    Ok(Duration::from_secs(1) / tick_rate)
+}
+
+/// Mesh transform as of the previous fixed tick, used to interpolate rendering.
+#[derive(Debug, Clone, Copy)]
+pub struct PreviousTransform {
+    pub translation: Translation3<f32>,
+    pub rotation: Rotation3<f32>,
+    pub scale: Scale3<f32>,
+}
+
+impl PreviousTransform {
+    pub fn of(mesh: &Mesh) -> Self {
+        Self {
+            translation: mesh.translation,
+            rotation: mesh.rotation,
+            scale: mesh.scale,
+        }
+    }
+    /// Blend from this transform towards `mesh`'s current one.
+    pub fn lerp(&self, mesh: &Mesh, alpha: f32) -> (Translation3<f32>, Rotation3<f32>, Scale3<f32>) {
+        let translation = self.translation.vector.lerp(&mesh.translation.vector, alpha);
+        let scale = self.scale.vector.lerp(&mesh.scale.vector, alpha);
+        let rotation = self
+            .rotation
+            .try_slerp(&mesh.rotation, alpha, f32::EPSILON)
+            .unwrap_or(mesh.rotation);
+        (translation.into(), rotation, scale.into())
+    }
+}
+
+/// Record every mesh's transform before a fixed tick moves it.
+pub fn snapshot_transforms(scene: &mut Scene) {
+    let mut missing: Vec<(Entity, PreviousTransform)> = Vec::new();
+    for (entity, mesh, previous) in scene.query::<(Entity, &Mesh, Option<&mut PreviousTransform>)>() {
+        match previous {
+            Some(previous) => *previous = PreviousTransform::of(mesh),
+            None => missing.push((entity, PreviousTransform::of(mesh))),
+        }
+    }
+    for (entity, previous) in missing {
+        scene.insert(entity, previous);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::entity::Entity;
+use crate::query::{Fetch, Query, ReadOnlyFetch};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Sparse-set storage for a single component type.
+///
+/// Components are packed densely so iteration is cheap, while `sparse` maps an
+/// entity's slot index to its position in the dense arrays. Removing a component
+/// swaps the last element into the hole, so only the moved entity's sparse slot
+/// has to be patched and every other entity keeps a valid mapping.
+#[derive(Debug)]
+pub struct ComponentStorage<T> {
+    sparse: Vec<Option<usize>>,
+    dense: Vec<T>,
+    owners: Vec<Entity>,
+}
+
+impl<T> ComponentStorage<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            owners: Vec::new(),
+        }
+    }
+    /// Position of `entity`'s component in the dense array, if it has one.
+    pub fn index_of(&self, entity: Entity) -> Option<usize> {
+        let idx = (*self.sparse.get(entity.index())?)?;
+        if self.owners[idx] == entity {
+            Some(idx)
+        } else {
+            None
+        }
+    }
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.index_of(entity).is_some()
+    }
+    /// Attach `value` to `entity`, returning the component it replaced.
+    pub fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(idx) = self.index_of(entity) {
+            return Some(std::mem::replace(&mut self.dense[idx], value));
+        }
+        if self.sparse.len() <= entity.index() {
+            self.sparse.resize(entity.index() + 1, None);
+        }
+        // a stale component from a previous generation in this slot is dropped
+        if let Some(idx) = self.sparse[entity.index()] {
+            self.swap_remove(idx);
+        }
+        self.sparse[entity.index()] = Some(self.dense.len());
+        self.dense.push(value);
+        self.owners.push(entity);
+        None
+    }
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        let idx = self.index_of(entity)?;
+        Some(self.swap_remove(idx))
+    }
+    fn swap_remove(&mut self, idx: usize) -> T {
+        let owner = self.owners.swap_remove(idx);
+        let value = self.dense.swap_remove(idx);
+        self.sparse[owner.index()] = None;
+        if let Some(moved) = self.owners.get(idx) {
+            self.sparse[moved.index()] = Some(idx);
+        }
+        value
+    }
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let idx = self.index_of(entity)?;
+        Some(&self.dense[idx])
+    }
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        let idx = self.index_of(entity)?;
+        Some(&mut self.dense[idx])
+    }
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+    /// Entities owning a component, in the same order as `values`.
+    pub fn entities(&self) -> &[Entity] {
+        &self.owners
+    }
+    pub fn values(&self) -> &[T] {
+        &self.dense
+    }
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.dense
+    }
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut T {
+        self.dense.as_mut_ptr()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.owners.iter().copied().zip(self.dense.iter())
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.owners.iter().copied().zip(self.dense.iter_mut())
+    }
+}
+
+impl<T> Default for ComponentStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Type-erased view of a `ComponentStorage<T>` so the world can keep storages
+/// of every component type in one map.
+pub trait AnyStorage: Any {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn contains(&self, entity: Entity) -> bool;
+    /// Drop `entity`'s component, if any.
+    fn remove_entity(&mut self, entity: Entity);
+}
+
+impl<T: 'static> AnyStorage for ComponentStorage<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+    fn contains(&self, entity: Entity) -> bool {
+        ComponentStorage::contains(self, entity)
+    }
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+}
+
+/// Owns every entity and its components.
+///
+/// Entities are generational handles: despawning bumps the slot's generation so
+/// any copies of the old handle stop resolving, and the slot is reused by the
+/// next `spawn`. Components are keyed by type, so any `'static` type can be
+/// attached to an entity without registering it first.
+///
+/// Resources are singletons that belong to the world rather than to an entity
+/// (the keymap, the camera, frame timing), also keyed by type.
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    components: HashMap<TypeId, Box<dyn AnyStorage>>,
+    resources: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free: Vec::new(),
+            components: HashMap::new(),
+            resources: HashMap::new(),
+        }
+    }
+    pub fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            return Entity::from_raw(index, self.generations[index as usize]);
+        }
+        let index = self.generations.len() as u32;
+        self.generations.push(0);
+        self.alive.push(true);
+        Entity::from_raw(index, 0)
+    }
+    /// Remove `entity` and all of its components. Returns false if the handle
+    /// was already stale.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        for storage in self.components.values_mut() {
+            storage.remove_entity(entity);
+        }
+
+        let index = entity.index();
+        self.alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        self.free.push(index as u32);
+        true
+    }
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index();
+        index < self.generations.len()
+            && self.alive[index]
+            && self.generations[index] == entity.generation()
+    }
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.generations
+            .iter()
+            .zip(self.alive.iter())
+            .enumerate()
+            .filter(|(_, (_, alive))| **alive)
+            .map(|(index, (generation, _))| Entity::from_raw(index as u32, *generation))
+    }
+    pub fn len(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Attach `component` to `entity`, returning the one it replaced. Does
+    /// nothing if the entity has been despawned.
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>().insert(entity, component)
+    }
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        self.components
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()?
+            .remove(entity)
+    }
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.storage::<T>()?.get(entity)
+    }
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.components
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()?
+            .get_mut(entity)
+    }
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.storage::<T>().is_some_and(|s| s.contains(entity))
+    }
+    /// Storage for components of type `T`, if any have ever been inserted.
+    pub fn storage<T: 'static>(&self) -> Option<&ComponentStorage<T>> {
+        self.components
+            .get(&TypeId::of::<T>())?
+            .as_any()
+            .downcast_ref::<ComponentStorage<T>>()
+    }
+    /// Storage for components of type `T`, created empty on first use.
+    pub fn storage_mut<T: 'static>(&mut self) -> &mut ComponentStorage<T> {
+        self.components
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(ComponentStorage::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .expect("component storage registered under the wrong type")
+    }
+    /// Every component of type `T`, densely packed.
+    pub fn values<T: 'static>(&self) -> &[T] {
+        self.storage::<T>().map_or(&[], |s| s.values())
+    }
+    pub(crate) fn storage_ptr<T: 'static>(&mut self) -> Option<*mut ComponentStorage<T>> {
+        self.components
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<ComponentStorage<T>>()
+            .map(|s| s as *mut ComponentStorage<T>)
+    }
+    pub(crate) fn storage_dyn<T: 'static>(&self) -> Option<*const dyn AnyStorage> {
+        self.components
+            .get(&TypeId::of::<T>())
+            .map(|s| s.as_ref() as *const dyn AnyStorage)
+    }
+    /// Store `resource`, returning the previous resource of the same type.
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) -> Option<R> {
+        self.resources
+            .insert(TypeId::of::<R>(), Box::new(resource))
+            .and_then(|r| r.downcast::<R>().ok())
+            .map(|r| *r)
+    }
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        self.resources
+            .remove(&TypeId::of::<R>())
+            .and_then(|r| r.downcast::<R>().ok())
+            .map(|r| *r)
+    }
+    pub fn resource<R: 'static>(&self) -> Option<&R> {
+        self.resources.get(&TypeId::of::<R>())?.downcast_ref::<R>()
+    }
+    pub fn resource_mut<R: 'static>(&mut self) -> Option<&mut R> {
+        self.resources.get_mut(&TypeId::of::<R>())?.downcast_mut::<R>()
+    }
+    pub fn has_resource<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+    /// Temporarily take resource `R` out of the world so `f` can use it
+    /// alongside mutable access to the world itself.
+    ///
+    /// Panics if `R` has not been inserted.
+    pub fn resource_scope<R: 'static, U>(&mut self, f: impl FnOnce(&mut World, &mut R) -> U) -> U {
+        let mut resource = self
+            .remove_resource::<R>()
+            .unwrap_or_else(|| panic!("resource {} does not exist", std::any::type_name::<R>()));
+        let result = f(self, &mut resource);
+        self.insert_resource(resource);
+        result
+    }
+    /// Iterate every entity matching `Q`, e.g. `(&mut Mesh, &Velocity)` or
+    /// `(Entity, &Mesh, Option<&Velocity>)`.
+    pub fn query<'w, Q: Fetch<'w>>(&'w mut self) -> Query<'w, Q> {
+        Query::new(self)
+    }
+    /// Like `query`, but for queries that only read, so it needs only a shared
+    /// borrow and several can be alive at once.
+    pub fn query_ref<'w, Q: ReadOnlyFetch<'w>>(&'w self) -> Query<'w, Q> {
+        Query::new_shared(self)
+    }
+    /// Fetch `Q` for a single entity, if it matches.
+    pub fn query_one<'w, Q: Fetch<'w>>(&'w mut self, entity: Entity) -> Option<Q::Item> {
+        Query::<Q>::new(self).single(entity).next()
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
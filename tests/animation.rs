@@ -0,0 +1,116 @@
+use flappy::animation::{self, Animation, AnimationError, AnimationEvents, PlayMode};
+use flappy::components::Mesh;
+use flappy::renderer::texture::Atlas;
+use flappy::scene::Scene;
+use flappy::time::Time;
+use std::time::Duration;
+
+fn atlas() -> Atlas {
+    Atlas::parse(64, 16, "a = [0, 0, 16, 16]\nb = [16, 0, 16, 16]\nc = [32, 0, 16, 16]\n").unwrap()
+}
+
+/// An animation at four frames a second, so quarter-second steps are exact.
+fn animation(frames: &[&str], mode: PlayMode) -> Animation {
+    Animation::new(&atlas(), frames.iter().copied(), 4.0, mode).unwrap()
+}
+
+/// The frame shown after each of `steps` quarter seconds, starting with the
+/// first.
+fn frames_shown(animation: &mut Animation, steps: usize) -> Vec<String> {
+    let mut shown = vec![animation.current().to_string()];
+    for _ in 0..steps {
+        animation.advance(0.25);
+        shown.push(animation.current().to_string());
+    }
+    shown
+}
+
+#[test]
+fn frames_must_be_in_the_atlas() {
+    let empty: [&str; 0] = [];
+    assert_eq!(
+        Animation::new(&atlas(), empty, 4.0, PlayMode::Loop).unwrap_err(),
+        AnimationError::NoFrames
+    );
+    assert_eq!(
+        Animation::new(&atlas(), ["a", "missing"], 4.0, PlayMode::Loop).unwrap_err(),
+        AnimationError::UnknownFrame("missing".to_string())
+    );
+}
+
+#[test]
+fn looping_starts_over_after_the_last_frame() {
+    let mut looping = animation(&["a", "b", "c"], PlayMode::Loop);
+    assert_eq!(frames_shown(&mut looping, 6), ["a", "b", "c", "a", "b", "c", "a"]);
+}
+
+#[test]
+fn ping_pong_plays_back_without_repeating_the_ends() {
+    let mut ping_pong = animation(&["a", "b", "c"], PlayMode::PingPong);
+    assert_eq!(frames_shown(&mut ping_pong, 8), ["a", "b", "c", "b", "a", "b", "c", "b", "a"]);
+    let mut single = animation(&["a"], PlayMode::PingPong);
+    assert_eq!(frames_shown(&mut single, 3), ["a", "a", "a", "a"]);
+}
+
+#[test]
+fn one_shots_stop_on_the_last_frame_and_finish_once() {
+    let mut once = animation(&["a", "b"], PlayMode::Once);
+    assert!(!once.advance(0.25));
+    assert_eq!(once.current(), "b");
+    assert!(!once.is_finished());
+    assert!(once.advance(0.25), "finishes when the last frame has played out");
+    assert!(once.is_finished());
+    assert!(!once.advance(0.25), "and only reports it once");
+    assert_eq!(once.current(), "b");
+
+    once.restart();
+    assert!(!once.is_finished());
+    assert_eq!(frames_shown(&mut once, 3), ["a", "b", "b", "b"]);
+}
+
+#[test]
+fn paused_animations_hold_their_frame() {
+    let mut paused = animation(&["a", "b"], PlayMode::Loop);
+    paused.playing = false;
+    assert_eq!(frames_shown(&mut paused, 2), ["a", "a", "a"]);
+}
+
+#[test]
+fn animate_points_meshes_at_the_current_frame_and_reports_finishes() {
+    let mut scene = Scene::new();
+    let entity = scene.spawn();
+    entity.add_mesh(&mut scene, flappy::game::bird_mesh());
+    entity.insert(&mut scene, animation(&["a", "b"], PlayMode::Once));
+    let mut time = Time::new();
+    time.advance(Duration::from_millis(250));
+    scene.insert_resource(time);
+
+    animation::animate(&mut scene);
+    let region = *atlas().region("b").unwrap();
+    let mesh = entity.get::<Mesh>(&scene).unwrap();
+    assert!(mesh.verts().iter().zip(region.uvs().iter().cycle()).all(|(vert, uv)| vert.uv == *uv));
+    assert!(scene.resource::<AnimationEvents>().unwrap().finished.is_empty());
+
+    animation::animate(&mut scene);
+    assert_eq!(scene.resource::<AnimationEvents>().unwrap().finished, [entity]);
+    animation::animate(&mut scene);
+    assert!(scene.resource::<AnimationEvents>().unwrap().finished.is_empty());
+}
+
+#[test]
+fn meshes_are_only_touched_when_the_frame_changes() {
+    let mut scene = Scene::new();
+    let entity = scene.spawn();
+    entity.add_mesh(&mut scene, flappy::game::bird_mesh());
+    entity.insert(&mut scene, animation(&["a", "b"], PlayMode::Loop));
+    let mut time = Time::new();
+    time.advance(Duration::from_millis(100));
+    scene.insert_resource(time);
+
+    animation::animate(&mut scene);
+    let generation = entity.get::<Mesh>(&scene).unwrap().generation();
+    animation::animate(&mut scene);
+    assert_eq!(entity.get::<Mesh>(&scene).unwrap().generation(), generation, "still on frame a");
+    animation::animate(&mut scene);
+    assert_ne!(entity.get::<Mesh>(&scene).unwrap().generation(), generation, "moved on to frame b");
+}
@@ -0,0 +1,33 @@
+use flappy::renderer::texture::{Atlas, AtlasError};
+use std::path::Path;
+
+#[test]
+fn regions_inside_the_image_are_named() {
+    let atlas = Atlas::parse(64, 32, "bird = [0, 0, 16, 16]\nedge = [48, 16, 16, 16]\n").unwrap();
+    assert_eq!(atlas.region("bird").unwrap().rect, [0, 0, 16, 16]);
+    assert_eq!(atlas.region("edge").unwrap().rect, [48, 16, 16, 16]);
+    assert!(atlas.region("missing").is_none());
+}
+
+#[test]
+fn regions_off_the_image_or_empty_are_rejected() {
+    let mut atlas = Atlas::new(64, 32);
+    for rect in [[0, 0, 0, 16], [0, 0, 16, 0], [60, 0, 8, 8], [0, 30, 8, 8]] {
+        assert!(matches!(atlas.insert("bad", rect), Err(AtlasError::OutOfBounds(_, r)) if r == rect));
+    }
+}
+
+#[test]
+fn regions_that_would_overflow_are_rejected() {
+    let mut atlas = Atlas::new(64, 32);
+    for rect in [[u32::MAX, 0, 2, 8], [0, u32::MAX, 8, 2], [1, 0, u32::MAX, 8], [0, 1, 8, u32::MAX]] {
+        assert!(matches!(atlas.insert("huge", rect), Err(AtlasError::OutOfBounds(..))), "{:?}", rect);
+    }
+    assert!(atlas.region("huge").is_none());
+}
+
+#[test]
+fn the_shipped_atlas_loads() {
+    let atlas = Atlas::from_path(Path::new("textures/sprites.png")).unwrap();
+    assert!(atlas.region("pipe").is_some());
+}
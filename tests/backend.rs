@@ -0,0 +1,265 @@
+use flappy::camera::Camera;
+use flappy::components::{Mesh, Sprite};
+use flappy::entity::Entity;
+use flappy::game::{self, Game};
+use flappy::image::Image;
+use flappy::renderer::backend::{Backend, DrawState, Geometry, PipelineDesc, VertexLayout};
+use flappy::renderer::layer::{Layer, RenderLayer};
+use flappy::renderer::recording::{Call, RecordingBackend};
+use flappy::renderer::target::{TargetDesc, TargetError};
+use flappy::renderer::texture::TextureFormat;
+use flappy::renderer::{Renderer, TransformMode};
+use flappy::save::SaveFile;
+use flappy::scene::Scene;
+use flappy::spawner::PipeSpawner;
+use nalgebra::Translation3;
+use std::time::Duration;
+
+/// A renderer recording into a `RecordingBackend`, set up to draw meshes and
+/// sprites.
+fn renderer(mode: TransformMode) -> Renderer {
+    let mut renderer = Renderer::with_backend(Box::new(RecordingBackend::new()));
+    let backend = renderer.backend_mut();
+    let texture = backend.create_texture(&Image::new(1, 1, vec![255; 4]));
+    let mesh_pipeline = backend.create_pipeline(&PipelineDesc::new("mesh.vs", "mesh.fs", VertexLayout::Mesh));
+    let sprite_pipeline = backend.create_pipeline(&PipelineDesc::new("sprite.vs", "sprite.fs", VertexLayout::Sprite));
+    renderer.texture = Some(texture);
+    renderer.mesh_pipeline = Some(mesh_pipeline);
+    renderer.sprite_pipeline = Some(sprite_pipeline);
+    renderer.transform_mode = mode;
+    renderer
+}
+
+fn recording(renderer: &Renderer) -> &RecordingBackend {
+    renderer.backend::<RecordingBackend>().unwrap()
+}
+
+/// A pipe mesh at `x`, so draws can be told apart by their model matrix.
+fn mesh_at(x: f32) -> Mesh {
+    let mut mesh = game::pipe_pair_mesh(PipeSpawner::default().gap);
+    mesh.translation = Translation3::new(x, 0.0, 0.0);
+    mesh
+}
+
+fn sprite_at(x: f32) -> Sprite {
+    let mut sprite = Sprite::new(game::sprite("ground"));
+    sprite.translation = Translation3::new(x, 0.0, 0.0);
+    sprite
+}
+
+/// Prepare and submit a frame of `meshes`, each with its entity and layer.
+fn render(renderer: &mut Renderer, meshes: &[(Entity, &Mesh, RenderLayer)], sprites: &[(Entity, &Sprite, RenderLayer)]) {
+    let meshes: Vec<_> = meshes.iter().map(|(e, m, l)| (*e, *m, None, Some(l))).collect();
+    let sprites: Vec<_> = sprites.iter().map(|(e, s, l)| (*e, *s, Some(l))).collect();
+    renderer.prepare(&meshes, &sprites, 1.0);
+    let mut scene = Scene::new();
+    scene.insert_resource(Camera::new(nalgebra::Point3::new(0.0, 3.0, 0.0), nalgebra::Point3::origin()));
+    renderer.newrender(&scene);
+}
+
+fn updates(backend: &RecordingBackend) -> usize {
+    backend.calls.iter().filter(|call| matches!(call, Call::UpdateBuffer(..))).count()
+}
+
+#[test]
+fn opaque_meshes_are_drawn_front_to_back_by_layer() {
+    let mut scene = Scene::new();
+    let [bird, pipes, ground] = [(); 3].map(|_| scene.spawn());
+    let meshes = [mesh_at(3.0), mesh_at(1.0), mesh_at(2.0)];
+    let mut renderer = renderer(TransformMode::Gpu);
+    render(
+        &mut renderer,
+        &[
+            (bird, &meshes[0], RenderLayer::new(Layer::Bird)),
+            (pipes, &meshes[1], RenderLayer::new(Layer::Pipes)),
+            (ground, &meshes[2], RenderLayer::new(Layer::Ground)),
+        ],
+        &[],
+    );
+    let frame = recording(&renderer).last_frame().unwrap();
+    let opaque = &frame.passes[0];
+    assert_eq!(opaque.state, DrawState::OPAQUE);
+    let xs: Vec<f32> = opaque.draws.iter().map(|draw| draw.model[(0, 3)]).collect();
+    assert_eq!(xs, [3.0, 2.0, 1.0]);
+    // nearer layers are lifted further towards the camera
+    let ys: Vec<f32> = opaque.draws.iter().map(|draw| draw.model[(1, 3)]).collect();
+    assert!(ys[0] > ys[1] && ys[1] > ys[2]);
+}
+
+#[test]
+fn transparent_sprites_are_blended_back_to_front_after_the_opaque_pass() {
+    let mut scene = Scene::new();
+    let [pipes, front, back] = [(); 3].map(|_| scene.spawn());
+    let mesh = mesh_at(0.0);
+    let sprites = [sprite_at(1.0), sprite_at(2.0)];
+    let mut renderer = renderer(TransformMode::Gpu);
+    render(
+        &mut renderer,
+        &[(pipes, &mesh, RenderLayer::new(Layer::Pipes))],
+        &[
+            (front, &sprites[0], RenderLayer::new(Layer::Overlay).transparent()),
+            (back, &sprites[1], RenderLayer::new(Layer::Hud).transparent()),
+        ],
+    );
+    let backend = recording(&renderer);
+    let frame = backend.last_frame().unwrap();
+    assert_eq!(frame.passes.len(), 2);
+    assert_eq!(frame.passes[0].draws.len(), 1);
+    assert_eq!(frame.passes[1].state, DrawState::TRANSPARENT);
+    // neighbouring instances share one draw call
+    let [draw] = frame.passes[1].draws.as_slice() else {
+        panic!("expected one blended draw, got {:?}", frame.passes[1].draws);
+    };
+    let Geometry::Instanced { instances, first: 0, count: 2 } = draw.geometry else {
+        panic!("expected both sprites instanced, got {:?}", draw.geometry);
+    };
+    let xs: Vec<f32> = backend.instances(instances).unwrap().iter().map(|i| i.model[(0, 3)]).collect();
+    assert_eq!(xs, [2.0, 1.0]);
+}
+
+#[test]
+fn gpu_meshes_are_uploaded_only_when_they_change() {
+    let mut scene = Scene::new();
+    let entity = scene.spawn();
+    let mut mesh = mesh_at(0.0);
+    let mut renderer = renderer(TransformMode::Gpu);
+    let layer = RenderLayer::default();
+    render(&mut renderer, &[(entity, &mesh, layer)], &[]);
+    let first = updates(recording(&renderer));
+
+    // moving a mesh only changes its model matrix
+    mesh.translation = Translation3::new(5.0, 0.0, 0.0);
+    render(&mut renderer, &[(entity, &mesh, layer)], &[]);
+    // the sprite instances are written every frame
+    assert_eq!(updates(recording(&renderer)), first + 1);
+
+    mesh.verts_mut()[0].pos.x += 1.0;
+    render(&mut renderer, &[(entity, &mesh, layer)], &[]);
+    assert_eq!(updates(recording(&renderer)), first + 1 + 3);
+
+    // a new mesh in the entity's place is uploaded, even if it looks the same
+    let mesh = mesh_at(5.0);
+    render(&mut renderer, &[(entity, &mesh, layer)], &[]);
+    assert_eq!(updates(recording(&renderer)), first + 1 + 3 + 3);
+}
+
+#[test]
+fn buffers_of_meshes_no_longer_drawn_are_destroyed() {
+    let mut scene = Scene::new();
+    let [kept, removed] = [(); 2].map(|_| scene.spawn());
+    let meshes = [mesh_at(0.0), mesh_at(1.0)];
+    let layer = RenderLayer::default();
+    let mut renderer = renderer(TransformMode::Gpu);
+    render(&mut renderer, &[(kept, &meshes[0], layer), (removed, &meshes[1], layer)], &[]);
+    let live = recording(&renderer).live_buffers();
+
+    render(&mut renderer, &[(kept, &meshes[0], layer)], &[]);
+    let backend = recording(&renderer);
+    let destroyed = backend.calls.iter().filter(|call| matches!(call, Call::DestroyBuffer(_))).count();
+    assert_eq!(destroyed, 2);
+    assert_eq!(backend.live_buffers(), live - 2);
+    let frame = backend.last_frame().unwrap();
+    for draw in &frame.passes[0].draws {
+        let Geometry::Indexed { vertices, elements, .. } = draw.geometry else {
+            panic!("expected a mesh draw");
+        };
+        assert!(backend.is_live(vertices) && backend.is_live(elements));
+    }
+}
+
+#[test]
+fn cpu_mode_draws_from_one_batch() {
+    let mut scene = Scene::new();
+    let [a, b] = [(); 2].map(|_| scene.spawn());
+    let meshes = [mesh_at(0.0), mesh_at(1.0)];
+    let layer = RenderLayer::default();
+    let mut renderer = renderer(TransformMode::Cpu);
+    render(&mut renderer, &[(a, &meshes[0], layer), (b, &meshes[1], layer)], &[]);
+    let backend = recording(&renderer);
+    let draws = &backend.last_frame().unwrap().passes[0].draws;
+    assert_eq!(draws.len(), 2);
+    let buffers: Vec<_> = draws
+        .iter()
+        .map(|draw| match draw.geometry {
+            Geometry::Indexed { vertices, elements, first, count } => {
+                assert_eq!(backend.elements(elements).unwrap().len(), 2 * count);
+                (vertices, elements, first)
+            }
+            geometry => panic!("expected an indexed draw, got {:?}", geometry),
+        })
+        .collect();
+    assert_eq!((buffers[0].0, buffers[0].1), (buffers[1].0, buffers[1].1));
+    // front to back: the second mesh's elements come first
+    assert!(buffers[0].2 > buffers[1].2);
+}
+
+#[test]
+fn nothing_is_drawn_without_a_texture() {
+    let mut scene = Scene::new();
+    let entity = scene.spawn();
+    let mesh = mesh_at(0.0);
+    let mut renderer = renderer(TransformMode::Gpu);
+    renderer.texture = None;
+    render(&mut renderer, &[(entity, &mesh, RenderLayer::default())], &[]);
+    let frame = recording(&renderer).last_frame().unwrap();
+    assert!(frame.passes.iter().all(|pass| pass.draws.is_empty()));
+}
+
+#[test]
+fn a_game_frame_runs_without_a_gpu() {
+    let save = SaveFile::new(std::env::temp_dir().join("flappy-backend-save.toml"));
+    let mut game = Game::with_backend(Box::new(RecordingBackend::new()), save);
+    game.setup();
+    game.frame(Duration::ZERO);
+    let renderer = game.scene.resource::<Renderer>().unwrap();
+    let backend = recording(renderer);
+    let pipelines: Vec<_> = backend
+        .calls
+        .iter()
+        .filter_map(|call| match call {
+            Call::CreatePipeline(_, desc) => Some(desc.layout),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(pipelines, [VertexLayout::Mesh, VertexLayout::Sprite]);
+    let frame = backend.last_frame().expect("no frame submitted");
+    let camera = game.scene.resource::<Camera>().unwrap();
+    assert_eq!(frame.view, *camera.view());
+    // the bird and title banner are meshes, the ground tiles sprites
+    let draws = &frame.passes[0].draws;
+    assert!(draws.iter().any(|draw| matches!(draw.geometry, Geometry::Indexed { .. })));
+    assert!(draws.iter().any(|draw| matches!(draw.geometry, Geometry::Instanced { .. })));
+}
+
+#[test]
+fn frames_are_drawn_into_the_renderer_target() {
+    let mut renderer = renderer(TransformMode::Gpu);
+    let desc = TargetDesc::new(320, 180).with_depth(TextureFormat::DepthStencil);
+    let target = renderer.backend_mut().create_target(&desc).unwrap();
+    renderer.target = Some(target);
+    render(&mut renderer, &[], &[]);
+    assert_eq!(recording(&renderer).last_frame().unwrap().target, Some(target));
+
+    renderer.resize(640, 360);
+    let resized = recording(&renderer).target(target).unwrap();
+    assert_eq!((resized.width, resized.height), (640, 360));
+    assert_eq!(resized.depth, Some(TextureFormat::DepthStencil));
+    // a minimised window keeps the old size
+    renderer.resize(0, 0);
+    assert_eq!(recording(&renderer).target(target).unwrap().width, 640);
+}
+
+#[test]
+fn target_attachments_must_fit_their_formats() {
+    assert_eq!(TargetDesc::new(4, 4).with_depth(TextureFormat::DepthComponent).validate(), Ok(()));
+    assert_eq!(
+        TargetDesc::new(4, 4).with_color(TextureFormat::DepthStencil).validate(),
+        Err(TargetError::Format(TextureFormat::DepthStencil))
+    );
+    assert_eq!(
+        TargetDesc::new(4, 4).with_depth(TextureFormat::Rgba).validate(),
+        Err(TargetError::Format(TextureFormat::Rgba))
+    );
+    let mut backend = RecordingBackend::new();
+    assert_eq!(backend.create_target(&TargetDesc::new(0, 4)), Err(TargetError::Empty));
+}
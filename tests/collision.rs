@@ -0,0 +1,173 @@
+use flappy::broadphase::BroadPhase;
+use flappy::collision::{self, Bounds, Collider, CollisionEvents, Shape, ShapeKind};
+use flappy::entity::Entity;
+use flappy::game::{self, Ceiling, Ground, Pipe, Player};
+use flappy::scene::Scene;
+use flappy::spawner::PipeSpawner;
+use nalgebra::{Point2, Rotation3, Translation3, Vector2, Vector3};
+
+fn spawn_bird(scene: &mut Scene, x: f32, z: f32, pitch_degrees: f32) -> Entity {
+    let bird = scene.spawn();
+    let mut mesh = game::bird_mesh();
+    mesh.translation = Translation3::new(x, 0.0, z);
+    mesh.rotation = Rotation3::new(Vector3::y() * pitch_degrees.to_radians());
+    let collider = Collider::from_mesh(&mesh, ShapeKind::Obb);
+    bird.add_mesh(scene, mesh);
+    bird.insert(scene, collider);
+    bird.insert(scene, Player);
+    bird
+}
+
+/// Pipe pair whose left edge is at `x`; the spawner's default gap spans
+/// z = -40..40.
+fn spawn_pipes(scene: &mut Scene, x: f32) -> Entity {
+    let pipes = scene.spawn();
+    let mut mesh = game::pipe_pair_mesh(PipeSpawner::default().gap);
+    mesh.translation = Translation3::new(x, 0.0, 0.0);
+    let collider = Collider::from_mesh_quads(&mesh, ShapeKind::Aabb);
+    pipes.add_mesh(scene, mesh);
+    pipes.insert(scene, collider);
+    pipes.insert(scene, Pipe);
+    pipes
+}
+
+fn hits(scene: &mut Scene, a: Entity, b: Entity) -> bool {
+    collision::detect_collisions(scene);
+    scene
+        .resource::<CollisionEvents>()
+        .unwrap()
+        .involving(a)
+        .any(|c| c.other(a) == Some(b))
+}
+
+fn marked<T: 'static>(scene: &mut Scene) -> Entity {
+    scene.query::<Entity>().with::<T>().next().unwrap()
+}
+
+#[test]
+fn bird_in_gap_misses_pipes() {
+    let mut scene = Scene::new();
+    let pipes = spawn_pipes(&mut scene, 0.0);
+    let bird = spawn_bird(&mut scene, 13.0, 0.0, 0.0);
+    assert!(!hits(&mut scene, bird, pipes));
+}
+
+#[test]
+fn bird_hits_bottom_pipe() {
+    let mut scene = Scene::new();
+    let pipes = spawn_pipes(&mut scene, 0.0);
+    let bird = spawn_bird(&mut scene, 13.0, -40.0, 0.0);
+    assert!(hits(&mut scene, bird, pipes));
+
+    let contact = *scene.resource::<CollisionEvents>().unwrap().involving(bird).next().unwrap();
+    // orient the normal from the bird towards the pipe it hit
+    let normal = if contact.a == bird { contact.normal } else { -contact.normal };
+    assert!(normal.y < -0.99, "normal {:?}", normal);
+    assert!((contact.depth - 8.0).abs() < 1e-3, "depth {}", contact.depth);
+}
+
+#[test]
+fn bird_hits_top_pipe() {
+    let mut scene = Scene::new();
+    let pipes = spawn_pipes(&mut scene, 0.0);
+    let bird = spawn_bird(&mut scene, 13.0, 35.0, 0.0);
+    assert!(hits(&mut scene, bird, pipes));
+}
+
+#[test]
+fn bird_before_pipe_misses() {
+    let mut scene = Scene::new();
+    let pipes = spawn_pipes(&mut scene, 0.0);
+    let bird = spawn_bird(&mut scene, -9.0, -100.0, 0.0);
+    assert!(!hits(&mut scene, bird, pipes));
+    let bird = spawn_bird(&mut scene, -7.0, -100.0, 0.0);
+    assert!(hits(&mut scene, bird, pipes));
+}
+
+#[test]
+fn rotated_bird_corner_hits_pipe() {
+    let mut scene = Scene::new();
+    let pipes = spawn_pipes(&mut scene, 0.0);
+    // unrotated the bird's top edge sits at z = 38, below the top pipe
+    let level = spawn_bird(&mut scene, 13.0, 30.0, 0.0);
+    assert!(!hits(&mut scene, level, pipes));
+    scene.despawn(level);
+    // at 45 degrees a corner reaches 8 * sqrt(2) ~ 11.3 above the centre
+    let pitched = spawn_bird(&mut scene, 13.0, 30.0, 45.0);
+    assert!(hits(&mut scene, pitched, pipes));
+}
+
+#[test]
+fn bird_hits_ground_and_ceiling() {
+    let mut scene = Scene::new();
+    game::spawn_bounds(&mut scene);
+    let ground = marked::<Ground>(&mut scene);
+    let ceiling = marked::<Ceiling>(&mut scene);
+
+    let bird = spawn_bird(&mut scene, 0.0, 0.0, 0.0);
+    assert!(!hits(&mut scene, bird, ground));
+    assert!(!hits(&mut scene, bird, ceiling));
+    scene.despawn(bird);
+
+    let bird = spawn_bird(&mut scene, 0.0, -128.0, 0.0);
+    assert!(hits(&mut scene, bird, ground));
+    assert!(!hits(&mut scene, bird, ceiling));
+    scene.despawn(bird);
+
+    let bird = spawn_bird(&mut scene, 0.0, 128.0, 0.0);
+    assert!(hits(&mut scene, bird, ceiling));
+    assert!(!hits(&mut scene, bird, ground));
+}
+
+#[test]
+fn aabb_of_rotated_mesh_grows() {
+    let mut mesh = game::bird_mesh();
+    mesh.rotation = Rotation3::new(Vector3::y() * 45f32.to_radians());
+    let shape = Shape::fit(ShapeKind::Aabb, mesh.verts().iter().map(|v| v.pos));
+    let (min, max) = Bounds::from_shape(&shape, Some(&mesh)).aabb();
+    let expected = 8.0 * 2f32.sqrt();
+    assert!((max.x - expected).abs() < 1e-3 && (min.y + expected).abs() < 1e-3);
+}
+
+#[test]
+fn circle_overlaps() {
+    let circle = |x: f32, y: f32, radius: f32| Bounds::Circle {
+        center: Point2::new(x, y),
+        radius,
+    };
+    let square = Bounds::from_shape(
+        &Shape::Aabb {
+            center: Point2::new(0.0, 0.0),
+            half_extents: Vector2::new(1.0, 1.0),
+        },
+        None,
+    );
+    assert!(circle(0.0, 0.0, 1.0).intersect(&circle(1.5, 0.0, 1.0)).is_some());
+    assert!(circle(0.0, 0.0, 1.0).intersect(&circle(2.5, 0.0, 1.0)).is_none());
+    assert!(square.intersect(&circle(1.5, 0.0, 1.0)).is_some());
+    // diagonal: inside the square's bounding axes but clear of its corner
+    assert!(square.intersect(&circle(1.6, 1.6, 0.8)).is_none());
+    assert!(square.intersect(&circle(1.5, 1.5, 0.8)).is_some());
+}
+
+#[test]
+fn sweep_and_prune_matches_brute_force() {
+    let mut scene = Scene::new();
+    game::spawn_bounds(&mut scene);
+    for i in 0..6 {
+        spawn_pipes(&mut scene, i as f32 * 40.0);
+    }
+    for i in 0..40 {
+        let x = (i * 7 % 260) as f32 - 10.0;
+        let z = (i * 53 % 270) as f32 - 135.0;
+        spawn_bird(&mut scene, x, z, (i * 15) as f32);
+    }
+    let bounds = collision::collect_bounds(&mut scene);
+    let key = |c: &collision::Contact| (c.a.index().min(c.b.index()), c.a.index().max(c.b.index()));
+    let mut naive: Vec<_> = collision::find_contacts(&bounds, BroadPhase::BruteForce).iter().map(key).collect();
+    let mut swept: Vec<_> = collision::find_contacts(&bounds, BroadPhase::SweepAndPrune).iter().map(key).collect();
+    naive.sort();
+    swept.sort();
+    assert!(!naive.is_empty());
+    assert_eq!(naive, swept);
+}
@@ -0,0 +1,157 @@
+use flappy::config::{self, ConfigError, ConfigWatcher, GameConfig};
+use flappy::scene::Scene;
+use flappy::time::Time;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+fn problems(text: &str) -> Vec<String> {
+    match GameConfig::parse(text) {
+        Err(ConfigError::Invalid(problems)) => problems,
+        other => panic!("expected the config to be rejected, got {:?}", other),
+    }
+}
+
+#[test]
+fn shipped_config_is_valid() {
+    GameConfig::from_path(Path::new("config/game.toml")).unwrap();
+    assert_eq!(GameConfig::parse("").unwrap(), GameConfig::default());
+}
+
+#[test]
+fn bird_fields_are_checked() {
+    assert_eq!(
+        problems("[bird]\nflap_velocity = 0"),
+        ["bird.flap_velocity must be positive, got 0"]
+    );
+    assert_eq!(
+        problems("[bird]\ngravity = 10"),
+        ["bird.gravity must be negative (downwards), got 10"]
+    );
+    assert_eq!(
+        problems("[bird]\nmax_pitch = 200"),
+        ["bird.max_pitch must be between 0 and 180 degrees, got 200"]
+    );
+    assert_eq!(
+        problems("[bird]\nflap_pitch = -100"),
+        ["bird.flap_pitch (-100) must be within bird.max_pitch (90)"]
+    );
+    assert_eq!(
+        problems("[bird]\ndive_acceleration = inf"),
+        ["bird.dive_acceleration must be a finite number, got inf"]
+    );
+}
+
+#[test]
+fn play_area_and_camera_fields_are_checked() {
+    assert_eq!(
+        problems("[play_area]\nfloor = 10\nceiling = -10"),
+        ["play_area.floor (10) must be below play_area.ceiling (-10)"]
+    );
+    assert_eq!(
+        problems("[play_area]\nceiling = nan"),
+        [
+            "play_area.ceiling must be a finite number, got NaN",
+            "play_area.floor (-128) must be below play_area.ceiling (NaN)",
+        ]
+    );
+    assert_eq!(problems("[camera]\nzoom = -1"), ["camera.zoom must be positive, got -1"]);
+}
+
+#[test]
+fn every_problem_is_reported_together() {
+    let text = "
+        [bird]
+        flap_velocity = -1
+        gravity = 1
+        max_pitch = 200
+        flap_pitch = 300
+
+        [play_area]
+        floor = 1
+        ceiling = 0
+
+        [camera]
+        zoom = 0
+    ";
+    let problems = problems(text);
+    assert_eq!(problems.len(), 6, "{:?}", problems);
+    let message = ConfigError::Invalid(problems.clone()).to_string();
+    assert_eq!(message.lines().collect::<Vec<_>>(), problems);
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    assert!(matches!(GameConfig::parse("[bird]\ngravty = -10"), Err(ConfigError::Parse(_))));
+}
+
+/// A config file of its own for one test.
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("flappy-config-{}", std::process::id()))
+        .join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir.join("game.toml")
+}
+
+/// Write `text` to `path`, dating it `age` seconds after now so the change is
+/// seen even on file systems with coarse timestamps.
+fn edit(path: &Path, text: &str, age: u64) {
+    fs::write(path, text).unwrap();
+    let file = fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(age)).unwrap();
+}
+
+/// Run `reload_config` `dt` after the last frame.
+fn reload(scene: &mut Scene, dt: Duration) -> bool {
+    let mut time = Time::new();
+    time.advance(dt);
+    scene.insert_resource(time);
+    config::reload_config(scene)
+}
+
+fn gravity(scene: &Scene) -> f32 {
+    scene.resource::<GameConfig>().unwrap().bird.gravity
+}
+
+#[test]
+fn edits_on_disk_are_reloaded() {
+    let path = scratch("reload");
+    edit(&path, "[bird]\ngravity = -1000", 0);
+    let watcher = ConfigWatcher::new(&path);
+    let mut scene = Scene::new();
+    scene.insert_resource(watcher.load());
+    let interval = watcher.interval;
+    scene.insert_resource(watcher);
+    assert_eq!(gravity(&scene), -1000.0);
+
+    edit(&path, "[bird]\ngravity = -900", 10);
+    assert!(!reload(&mut scene, interval / 2), "the file is only checked every interval");
+    assert!(reload(&mut scene, interval / 2));
+    assert_eq!(gravity(&scene), -900.0);
+    assert!(!reload(&mut scene, interval), "nothing changed since");
+}
+
+#[test]
+fn invalid_edits_keep_the_running_config() {
+    let path = scratch("invalid");
+    edit(&path, "[bird]\ngravity = -1000", 0);
+    let watcher = ConfigWatcher::new(&path);
+    let mut scene = Scene::new();
+    scene.insert_resource(watcher.load());
+    let interval = watcher.interval;
+    scene.insert_resource(watcher);
+
+    edit(&path, "[bird]\ngravity = 5", 10);
+    assert!(!reload(&mut scene, interval));
+    assert_eq!(gravity(&scene), -1000.0);
+    edit(&path, "[bird]\ngravity = ", 20);
+    assert!(!reload(&mut scene, interval));
+    assert_eq!(gravity(&scene), -1000.0);
+
+    // fixing the file is picked up again
+    edit(&path, "[bird]\ngravity = -800", 30);
+    assert!(reload(&mut scene, interval));
+    assert_eq!(gravity(&scene), -800.0);
+}
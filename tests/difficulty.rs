@@ -0,0 +1,97 @@
+use flappy::difficulty::{Curve, Difficulty, DifficultyError};
+
+const VALID: &str = "
+speed = [[0, 48], [10, 56]]
+gap = [[0, 80]]
+spacing = [[0, 160]]
+variance = [[0, 40]]
+";
+
+fn problems(text: &str) -> Vec<String> {
+    match Difficulty::parse(text) {
+        Err(DifficultyError::Invalid(problems)) => problems,
+        other => panic!("expected the curves to be rejected, got {:?}", other),
+    }
+}
+
+#[test]
+fn curves_interpolate_between_points() {
+    let curve = Curve::new(vec![[0.0, 10.0], [10.0, 20.0], [20.0, 0.0]]).unwrap();
+    assert_eq!(curve.sample(0.0), 10.0);
+    assert_eq!(curve.sample(5.0), 15.0);
+    assert_eq!(curve.sample(10.0), 20.0);
+    assert_eq!(curve.sample(15.0), 10.0);
+}
+
+#[test]
+fn curves_hold_flat_past_either_end() {
+    let curve = Curve::new(vec![[5.0, 1.0], [10.0, 3.0]]).unwrap();
+    assert_eq!(curve.sample(-100.0), 1.0);
+    assert_eq!(curve.sample(4.9), 1.0);
+    assert_eq!(curve.sample(10.0), 3.0);
+    assert_eq!(curve.sample(1e9), 3.0);
+    assert_eq!(Curve::constant(7.0).sample(-3.0), 7.0);
+}
+
+#[test]
+fn curve_points_must_be_finite_and_increasing() {
+    assert!(Curve::new(vec![]).is_err());
+    assert!(Curve::new(vec![[0.0, f32::NAN]]).is_err());
+    assert!(Curve::new(vec![[f32::INFINITY, 1.0]]).is_err());
+    assert!(Curve::new(vec![[1.0, 1.0], [1.0, 2.0]]).is_err());
+    assert!(Curve::new(vec![[2.0, 1.0], [1.0, 2.0]]).is_err());
+    assert!(Difficulty::parse(&VALID.replace("[[0, 48], [10, 56]]", "[[10, 48], [0, 56]]")).is_err());
+}
+
+#[test]
+fn valid_curves_parse() {
+    let difficulty = Difficulty::parse(VALID).unwrap();
+    assert_eq!(difficulty.speed.sample(5.0), 52.0);
+    let shipped = std::fs::read_to_string("config/difficulty.toml").unwrap();
+    Difficulty::parse(&shipped).unwrap();
+}
+
+#[test]
+fn spacing_must_be_positive() {
+    assert_eq!(
+        problems(&VALID.replace("spacing = [[0, 160]]", "spacing = [[0, 160], [10, 0]]")),
+        ["spacing must be positive, got 0 at 10"]
+    );
+}
+
+#[test]
+fn gap_must_be_positive() {
+    assert_eq!(
+        problems(&VALID.replace("gap = [[0, 80]]", "gap = [[0, -1]]")),
+        ["gap must be positive, got -1 at 0"]
+    );
+}
+
+#[test]
+fn variance_must_not_be_negative() {
+    assert!(Difficulty::parse(&VALID.replace("variance = [[0, 40]]", "variance = [[0, 0]]")).is_ok());
+    assert_eq!(
+        problems(&VALID.replace("variance = [[0, 40]]", "variance = [[0, -5]]")),
+        ["variance must not be negative, got -5 at 0"]
+    );
+}
+
+#[test]
+fn speed_must_not_be_negative() {
+    assert!(Difficulty::parse(&VALID.replace("[[0, 48], [10, 56]]", "[[0, 0]]")).is_ok());
+    assert_eq!(
+        problems(&VALID.replace("[[0, 48], [10, 56]]", "[[0, 48], [10, -56]]")),
+        ["speed must not be negative, got -56 at 10"]
+    );
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let text = "
+        speed = [[0, -1]]
+        gap = [[0, 0]]
+        spacing = [[0, -160], [5, 0]]
+        variance = [[0, -1]]
+    ";
+    assert_eq!(problems(text).len(), 5);
+}
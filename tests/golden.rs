@@ -0,0 +1,165 @@
+use flappy::camera::Camera;
+use flappy::game::Game;
+use flappy::golden::Golden;
+use flappy::headless::Headless;
+use flappy::image::Image;
+use flappy::renderer::backend::Backend;
+use flappy::renderer::opengl::GlBackend;
+use flappy::renderer::post::PostChain;
+use flappy::renderer::target::TargetDesc;
+use flappy::renderer::texture::TextureFormat;
+use flappy::renderer::Renderer;
+use flappy::renderer::software::SoftwareRenderer;
+use flappy::save::SaveFile;
+use flappy::spawner::PipeSpawner;
+use flappy::state::GameState;
+use std::path::Path;
+use std::time::Duration;
+
+const WIDTH: u32 = 480;
+const HEIGHT: u32 = 270;
+
+/// Pixels the software renderer may differ from GL by, a tenth of a percent.
+const MAX_ROUNDING_MISMATCHES: usize = (WIDTH * HEIGHT / 1000) as usize;
+
+/// A headless context, or `None` (with a note) on machines without EGL, so
+/// the rest of the suite still runs there.
+fn headless() -> Option<Headless> {
+    match Headless::new(WIDTH, HEIGHT) {
+        Ok(headless) => Some(headless),
+        Err(e) => {
+            eprintln!("skipping golden test, no headless GL context: {}", e);
+            None
+        }
+    }
+}
+
+/// A game showing what the window would at 1920x1080, scaled down.
+fn game() -> Game {
+    game_on(Box::new(GlBackend::new()))
+}
+
+/// Like `game`, drawing with `backend`.
+fn game_on(backend: Box<dyn Backend>) -> Game {
+    let save = SaveFile::new(std::env::temp_dir().join("flappy-golden-save.toml"));
+    let mut game = Game::with_backend(backend, save);
+    let camera = game.scene.resource_mut::<Camera>().unwrap();
+    camera.resize(WIDTH, HEIGHT);
+    camera.set_zoom(5.0 * WIDTH as f32 / 1920.0);
+    game.setup();
+    game
+}
+
+fn check(name: &str, image: &Image) {
+    if let Err(e) = Golden::new("tests/golden").check(name, image) {
+        panic!("{}", e);
+    }
+}
+
+/// Check the software renderer draws what GL does once `set_up` has run on a
+/// game with each.
+fn check_software(headless: &Headless, name: &str, set_up: impl Fn(&mut Game)) {
+    set_up(&mut game());
+    let gl = headless.read_pixels();
+    let mut game = game_on(Box::new(SoftwareRenderer::new(WIDTH, HEIGHT)));
+    set_up(&mut game);
+    let renderer = game.scene.resource::<Renderer>().unwrap();
+    let software = renderer.backend::<SoftwareRenderer>().unwrap().image();
+    let comparison = software.compare(&gl, 2);
+    // nearest sampling may round the other way where a pixel centre lands
+    // right on a texel edge
+    if comparison.mismatched > MAX_ROUNDING_MISMATCHES {
+        std::fs::create_dir_all("target/golden").unwrap();
+        let actual = format!("target/golden/{}.software.png", name);
+        software.save(Path::new(&actual)).unwrap();
+        comparison.diff.save(Path::new(&format!("target/golden/{}.software-diff.png", name))).unwrap();
+        panic!(
+            "{}: {} pixels differ between the software and GL renderers; see {}",
+            name, comparison.mismatched, actual
+        );
+    }
+}
+
+#[test]
+fn title_screen() {
+    let Some(headless) = headless() else {
+        return;
+    };
+    let mut game = game();
+    game.frame(Duration::ZERO);
+    let image = headless.read_pixels();
+    if let Err(e) = Golden::new("tests/golden").check("title_screen", &image) {
+        panic!("{}", e);
+    }
+}
+
+#[test]
+fn title_screen_in_software() {
+    let Some(headless) = headless() else {
+        return;
+    };
+    check_software(&headless, "title_screen", |game| game.frame(Duration::ZERO));
+}
+
+#[test]
+fn layers_over_pipes() {
+    let Some(headless) = headless() else {
+        return;
+    };
+    let mut game = game();
+    game.set_state(GameState::GetReady);
+    game.frame(Duration::ZERO);
+    // a pair behind the bird and under the banners
+    let spawner = game.scene.resource::<PipeSpawner>().unwrap().clone();
+    spawner.spawn_pair(&mut game.scene, -8.0, -20.0);
+    game.frame(Duration::ZERO);
+    let image = headless.read_pixels();
+    if let Err(e) = Golden::new("tests/golden").check("layers_over_pipes", &image) {
+        panic!("{}", e);
+    }
+}
+
+#[test]
+fn layers_over_pipes_in_software() {
+    let Some(headless) = headless() else {
+        return;
+    };
+    check_software(&headless, "layers_over_pipes", |game| {
+        game.set_state(GameState::GetReady);
+        game.frame(Duration::ZERO);
+        let spawner = game.scene.resource::<PipeSpawner>().unwrap().clone();
+        spawner.spawn_pair(&mut game.scene, -8.0, -20.0);
+        game.frame(Duration::ZERO);
+    });
+}
+
+#[test]
+fn render_target_matches_golden_after_resize() {
+    // kept alive as the context the target draws with
+    let Some(_headless) = headless() else {
+        return;
+    };
+    let mut game = game();
+    let renderer = game.scene.resource_mut::<Renderer>().unwrap();
+    let desc = TargetDesc::new(16, 16).with_depth(TextureFormat::DepthComponent);
+    renderer.target = Some(renderer.backend_mut().create_target(&desc).unwrap());
+    renderer.resize(WIDTH, HEIGHT);
+    game.frame(Duration::ZERO);
+    let renderer = game.scene.resource_mut::<Renderer>().unwrap();
+    let target = renderer.target.unwrap();
+    check("title_screen", &renderer.backend_mut().read_target(target));
+}
+
+#[test]
+fn post_effects_match_golden() {
+    let Some(headless) = headless() else {
+        return;
+    };
+    let mut game = game();
+    let post = game.scene.resource_mut::<PostChain>().unwrap();
+    for effect in &mut post.effects {
+        effect.enabled = true;
+    }
+    game.frame(Duration::ZERO);
+    check("title_screen_post", &headless.read_pixels());
+}
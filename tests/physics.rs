@@ -0,0 +1,79 @@
+use flappy::components::{Mesh, Velocity};
+use flappy::game::bird_mesh;
+use flappy::physics::{self, Integrator};
+use flappy::scene::Scene;
+use flappy::time::Time;
+use nalgebra::Vector3;
+use std::time::Duration;
+
+const INTEGRATORS: [Integrator; 3] = [Integrator::ExplicitEuler, Integrator::SemiImplicitEuler, Integrator::Verlet];
+
+fn gravity() -> Vector3<f32> {
+    Vector3::new(0.0, 0.0, -9.8)
+}
+
+/// Where a body thrown up and sideways is after `steps` of `dt` seconds.
+fn thrown(integrator: Integrator, dt: f32, steps: usize) -> (Vector3<f32>, Vector3<f32>) {
+    let (mut position, mut velocity) = (Vector3::zeros(), Vector3::new(1.0, 0.0, 4.0));
+    for _ in 0..steps {
+        integrator.step(&mut position, &mut velocity, &gravity(), dt);
+    }
+    (position, velocity)
+}
+
+/// Run `physics::integrate` once, `dt` seconds after the last frame.
+fn integrate(scene: &mut Scene, dt: Duration) {
+    let mut time = Time::new();
+    time.advance(dt);
+    scene.insert_resource(time);
+    physics::integrate(scene);
+}
+
+#[test]
+fn halving_the_step_lands_in_the_same_place() {
+    for integrator in INTEGRATORS {
+        let (position, velocity) = thrown(integrator, 1.0 / 60.0, 1);
+        let (halved, halved_velocity) = thrown(integrator, 1.0 / 120.0, 2);
+        assert!((position - halved).norm() < 1e-3, "{:?}: {} vs {}", integrator, position, halved);
+        assert!((velocity - halved_velocity).norm() < 1e-5, "{:?}", integrator);
+    }
+}
+
+#[test]
+fn verlet_is_exact_under_constant_acceleration() {
+    let (mut position, mut velocity) = (Vector3::zeros(), Vector3::new(1.0, 0.0, 4.0));
+    let mut t = 0.0;
+    // uneven steps, as a stuttering frame rate gives
+    for dt in [0.01, 0.05, 0.002, 0.1, 0.033] {
+        Integrator::Verlet.step(&mut position, &mut velocity, &gravity(), dt);
+        t += dt;
+    }
+    let expected = Vector3::new(1.0, 0.0, 4.0) * t + gravity() * (0.5 * t * t);
+    assert!((position - expected).norm() < 1e-5, "{} vs {}", position, expected);
+}
+
+#[test]
+fn every_spinning_entity_is_rotated() {
+    let mut scene = Scene::new();
+    let spinner = scene.spawn();
+    spinner.add_mesh(&mut scene, bird_mesh());
+    spinner.add_rot_velocity(&mut scene, Vector3::new(0.0, 1.0, 0.0));
+    spinner.add_rot_acceleration(&mut scene, Vector3::new(0.0, 2.0, 0.0));
+    integrate(&mut scene, Duration::from_millis(500));
+    // semi-implicit: the spin picks up a second's worth before turning
+    let angle = spinner.get::<Mesh>(&scene).unwrap().rotation.angle();
+    assert!((angle - 1.0).abs() < 1e-5, "turned {}", angle);
+}
+
+#[test]
+fn velocity_moves_entities_without_acceleration() {
+    let mut scene = Scene::new();
+    let drifter = scene.spawn();
+    drifter.add_mesh(&mut scene, bird_mesh());
+    drifter.add_velocity(&mut scene, Vector3::new(-3.0, 0.0, 0.0));
+    let start = drifter.get::<Mesh>(&scene).unwrap().translation.vector;
+    integrate(&mut scene, Duration::from_millis(500));
+    let moved = drifter.get::<Mesh>(&scene).unwrap().translation.vector - start;
+    assert!((moved - Vector3::new(-1.5, 0.0, 0.0)).norm() < 1e-6, "moved {}", moved);
+    assert_eq!(drifter.get::<Velocity>(&scene).unwrap().velocity, Vector3::new(-3.0, 0.0, 0.0));
+}
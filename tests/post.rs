@@ -0,0 +1,234 @@
+use flappy::camera::Camera;
+use flappy::config::{ConfigError, GameConfig};
+use flappy::game::Game;
+use flappy::renderer::backend::{Backend, DrawState, Geometry, TargetId, Uniform, VertexLayout};
+use flappy::renderer::post::{Effect, Param, PostChain, FULL_SCREEN_VS};
+use flappy::renderer::recording::{Call, RecordingBackend};
+use flappy::renderer::target::TargetDesc;
+use flappy::renderer::Renderer;
+use flappy::save::SaveFile;
+use flappy::scene::Scene;
+use flappy::state::GameState;
+use std::path::Path;
+use std::time::Duration;
+
+fn scene(width: u32, height: u32) -> Scene {
+    let mut scene = Scene::new();
+    let mut camera = Camera::new(nalgebra::Point3::new(0.0, 3.0, 0.0), nalgebra::Point3::origin());
+    camera.resize(width, height);
+    scene.insert_resource(camera);
+    scene
+}
+
+fn recording(renderer: &Renderer) -> &RecordingBackend {
+    renderer.backend::<RecordingBackend>().unwrap()
+}
+
+fn targets_made(backend: &RecordingBackend) -> Vec<(TargetId, TargetDesc)> {
+    backend
+        .calls
+        .iter()
+        .filter_map(|call| match call {
+            Call::CreateTarget(id, desc) => Some((*id, *desc)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn shaders_loaded(backend: &RecordingBackend) -> Vec<String> {
+    backend
+        .calls
+        .iter()
+        .filter_map(|call| match call {
+            Call::CreatePipeline(_, desc) => {
+                assert_eq!(desc.layout, VertexLayout::FullScreen);
+                assert_eq!(desc.vertex_shader, Path::new(FULL_SCREEN_VS));
+                Some(desc.fragment_shader.display().to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn enabled_effects_run_in_order_each_reading_the_last() {
+    let mut renderer = Renderer::with_backend(Box::new(RecordingBackend::new()));
+    let mut post = PostChain::with_effects(vec![
+        Effect::new("first", "first.fs"),
+        Effect::new("skipped", "skipped.fs").disabled(),
+        Effect::new("second", "second.fs"),
+    ]);
+    post.render(&mut renderer, &scene(320, 180));
+
+    let backend = recording(&renderer);
+    let targets = targets_made(backend);
+    assert_eq!(targets.len(), 2);
+    let [(a, scene_desc), (b, _)] = [targets[0], targets[1]];
+    assert_eq!((scene_desc.width, scene_desc.height), (320, 180));
+    assert!(scene_desc.depth.is_some(), "the scene needs a depth buffer");
+    assert_eq!(shaders_loaded(backend), ["first.fs", "second.fs"]);
+
+    let frames: Vec<_> = backend.frames().collect();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].target, Some(a));
+    let passes: Vec<_> = frames[1..]
+        .iter()
+        .map(|frame| {
+            assert_eq!(frame.passes.len(), 1);
+            assert_eq!(frame.passes[0].state, DrawState::OVERWRITE);
+            let draw = &frame.passes[0].draws[0];
+            assert_eq!(draw.geometry, Geometry::FullScreen);
+            (draw.texture, frame.target)
+        })
+        .collect();
+    assert_eq!(
+        passes,
+        [(backend.target_texture(a), Some(b)), (backend.target_texture(b), None)]
+    );
+}
+
+#[test]
+fn with_no_effect_enabled_the_scene_is_drawn_directly() {
+    let mut renderer = Renderer::with_backend(Box::new(RecordingBackend::new()));
+    let mut post = PostChain::with_effects(vec![Effect::new("off", "off.fs").disabled()]);
+    post.render(&mut renderer, &scene(320, 180));
+    let backend = recording(&renderer);
+    assert!(targets_made(backend).is_empty());
+    assert_eq!(backend.frames().count(), 1);
+    assert_eq!(backend.last_frame().unwrap().target, None);
+}
+
+#[test]
+fn the_last_effect_draws_into_the_renderer_target() {
+    let mut renderer = Renderer::with_backend(Box::new(RecordingBackend::new()));
+    let output = renderer.backend_mut().create_target(&TargetDesc::new(320, 180)).unwrap();
+    renderer.target = Some(output);
+    let mut post = PostChain::with_effects(vec![Effect::new("only", "only.fs")]);
+    post.render(&mut renderer, &scene(320, 180));
+    assert_eq!(renderer.target, Some(output));
+    assert_eq!(recording(&renderer).last_frame().unwrap().target, Some(output));
+}
+
+#[test]
+fn params_are_passed_as_uniforms() {
+    let mut renderer = Renderer::with_backend(Box::new(RecordingBackend::new()));
+    let effect = Effect::new("grade", "grade.fs")
+        .with_param("strength", Param::Float(0.5))
+        .with_param("tint", Param::Vector(vec![1.0, 0.5, 0.25]))
+        .with_param("lut", Param::Texture("textures/grade.png".into()));
+    let mut post = PostChain::with_effects(vec![effect]);
+    let scene = scene(320, 180);
+    post.render(&mut renderer, &scene);
+    post.render(&mut renderer, &scene);
+
+    let backend = recording(&renderer);
+    let loaded: Vec<_> = backend
+        .calls
+        .iter()
+        .filter_map(|call| match call {
+            Call::CreateTexture(_, width, height) => Some((*width, *height)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(loaded, [(256, 16)], "the LUT should be loaded once");
+    let uniforms = &backend.last_frame().unwrap().passes[0].draws[0].uniforms;
+    let uniform = |name: &str| uniforms.iter().find(|(n, _)| n == name).map(|(_, u)| *u);
+    assert_eq!(uniform("resolution"), Some(Uniform::Vec2([320.0, 180.0])));
+    assert_eq!(uniform("time"), Some(Uniform::Float(0.0)));
+    assert_eq!(uniform("strength"), Some(Uniform::Float(0.5)));
+    assert_eq!(uniform("tint"), Some(Uniform::Vec3([1.0, 0.5, 0.25])));
+    assert!(matches!(uniform("lut"), Some(Uniform::Texture(_))));
+}
+
+#[test]
+fn targets_follow_the_camera_size() {
+    let mut renderer = Renderer::with_backend(Box::new(RecordingBackend::new()));
+    let mut post = PostChain::with_effects(vec![Effect::new("a", "a.fs"), Effect::new("b", "b.fs")]);
+    post.render(&mut renderer, &scene(320, 180));
+    post.render(&mut renderer, &scene(640, 360));
+    let backend = recording(&renderer);
+    assert_eq!(targets_made(backend).len(), 2);
+    assert_eq!(shaders_loaded(backend).len(), 2);
+    for (target, _) in targets_made(backend) {
+        let desc = backend.target(target).unwrap();
+        assert_eq!((desc.width, desc.height), (640, 360));
+    }
+}
+
+#[test]
+fn effects_are_toggled_by_name() {
+    let mut post = PostChain::with_effects(vec![Effect::new("vignette", "vignette.fs")]);
+    assert_eq!(post.toggle("vignette"), Some(false));
+    assert!(!post.is_active());
+    assert!(post.set_enabled("vignette", true));
+    assert!(post.is_active());
+    assert!(post.set_param("vignette", "strength", Param::Float(0.2)));
+    assert_eq!(post.effect("vignette").unwrap().params["strength"], Param::Float(0.2));
+    assert_eq!(post.toggle("missing"), None);
+    assert!(!post.set_param("missing", "strength", Param::Float(0.2)));
+}
+
+#[test]
+fn config_lists_effects_in_order() {
+    let config = GameConfig::parse(
+        r#"
+        [[post.effects]]
+        name = "scanlines"
+        shader = "shaders/post/scanlines.fs"
+        params = { intensity = 0.25, spacing = 4 }
+
+        [[post.effects]]
+        name = "flash"
+        shader = "shaders/post/flash.fs"
+        enabled = false
+        params = { color = [1.0, 0.0, 0.0], amount = 0.0 }
+        "#,
+    )
+    .unwrap();
+    let effects = &config.post.effects;
+    assert_eq!(effects.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["scanlines", "flash"]);
+    assert!(effects[0].enabled);
+    assert!(!effects[1].enabled);
+    assert_eq!(effects[0].params["spacing"], Param::Float(4.0));
+    assert_eq!(effects[1].params["color"], Param::Vector(vec![1.0, 0.0, 0.0]));
+}
+
+#[test]
+fn config_rejects_bad_effects() {
+    let result = GameConfig::parse(
+        r#"
+        [[post.effects]]
+        name = "flash"
+        shader = "shaders/post/flash.fs"
+        params = { color = [1.0, 0.0, 0.0, 1.0, 0.0] }
+
+        [[post.effects]]
+        name = "flash"
+        shader = "shaders/post/missing.fs"
+        params = { lut = "textures/missing.png" }
+        "#,
+    );
+    let Err(ConfigError::Invalid(problems)) = result else {
+        panic!("expected the config to be rejected, got {:?}", result);
+    };
+    assert_eq!(problems.len(), 4, "{:?}", problems);
+}
+
+#[test]
+fn dying_flashes_the_screen() {
+    let save = SaveFile::new(std::env::temp_dir().join("flappy-post-save.toml"));
+    let mut game = Game::with_backend(Box::new(RecordingBackend::new()), save);
+    game.setup();
+    let flash = |game: &Game| {
+        let effect = game.scene.resource::<PostChain>().unwrap().effect("flash").unwrap();
+        (effect.enabled, effect.params["amount"].clone())
+    };
+    assert_eq!(flash(&game), (false, Param::Float(0.0)), "the flash pass shouldn't run until needed");
+    game.set_state(GameState::Dying);
+    game.frame(Duration::ZERO);
+    assert_eq!(flash(&game), (true, Param::Float(1.0)));
+    for _ in 0..10 {
+        game.frame(Duration::from_millis(100));
+    }
+    assert_eq!(flash(&game), (false, Param::Float(0.0)));
+}
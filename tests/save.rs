@@ -0,0 +1,150 @@
+use flappy::save::{self, HighScore, SaveData, SaveError, SaveFile, MAX_HIGH_SCORES, TEST_VERSION, VERSION};
+use std::fs;
+use std::path::PathBuf;
+
+/// An empty directory for one test to keep its files in.
+fn scratch(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("flappy-save-{}", std::process::id()))
+        .join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn score(score: u32, date: &str) -> HighScore {
+    HighScore {
+        score,
+        date: date.to_string(),
+        seed: 0xdead_beef,
+    }
+}
+
+#[test]
+fn scores_are_ranked_best_first_with_ties_below() {
+    let mut data = SaveData::new();
+    assert_eq!(data.record(score(5, "first")), Some(0));
+    assert_eq!(data.record(score(9, "second")), Some(0));
+    assert_eq!(data.record(score(5, "third")), Some(2));
+    assert_eq!(data.record(score(7, "fourth")), Some(1));
+    let table: Vec<_> = data.high_scores.iter().map(|s| (s.score, s.date.as_str())).collect();
+    assert_eq!(table, [(9, "second"), (7, "fourth"), (5, "first"), (5, "third")]);
+    assert_eq!(data.best().map(|s| s.score), Some(9));
+}
+
+#[test]
+fn the_table_keeps_only_the_best_scores() {
+    let mut data = SaveData::new();
+    for i in 0..MAX_HIGH_SCORES as u32 {
+        data.record(score(10 + i, "old"));
+    }
+    assert_eq!(data.record(score(10, "too low")), None, "ties with the last place don't make it");
+    assert_eq!(data.record(score(0, "too low")), None);
+    assert_eq!(data.high_scores.len(), MAX_HIGH_SCORES);
+    assert_eq!(data.record(score(100, "new")), Some(0));
+    assert_eq!(data.high_scores.len(), MAX_HIGH_SCORES);
+    assert_eq!(data.high_scores.last().unwrap().score, 11, "the lowest score drops off");
+}
+
+#[test]
+fn saves_round_trip_through_toml() {
+    let mut data = SaveData::new();
+    data.record(score(3, "2024-01-02"));
+    data.settings.seed = Some(u64::MAX);
+    data.settings.fullscreen = true;
+    assert_eq!(SaveData::parse(&data.to_toml()).unwrap(), data);
+}
+
+#[test]
+fn parsing_sorts_and_trims_the_table() {
+    let mut text = format!("version = {}\n", VERSION);
+    for i in 0..MAX_HIGH_SCORES + 2 {
+        text += &format!("[[high_scores]]\nscore = {}\ndate = \"d\"\nseed = \"0\"\n", i);
+    }
+    let data = SaveData::parse(&text).unwrap();
+    assert_eq!(data.high_scores.len(), MAX_HIGH_SCORES);
+    assert_eq!(data.best().unwrap().score, MAX_HIGH_SCORES as u32 + 1);
+}
+
+#[test]
+fn versions_are_checked() {
+    assert!(matches!(SaveData::parse(""), Err(SaveError::MissingVersion)));
+    assert!(matches!(SaveData::parse("version = \"1\""), Err(SaveError::MissingVersion)));
+    assert!(matches!(SaveData::parse("version = -1"), Err(SaveError::MissingVersion)));
+    assert!(matches!(SaveData::parse("version = 0"), Err(SaveError::Version(0))));
+    let newer = VERSION + 1;
+    assert!(matches!(SaveData::parse(&format!("version = {}", newer)), Err(SaveError::Version(v)) if v == newer));
+    assert_eq!(SaveData::parse(&format!("version = {}", VERSION)).unwrap(), SaveData::new());
+}
+
+#[test]
+fn older_tables_are_migrated_a_step_at_a_time() {
+    let mut table: toml::Table = format!(
+        "version = {}\n[[scores]]\nscore = 4\ndate = \"2024-01-02\"\nseed = \"0\"\n",
+        TEST_VERSION
+    )
+    .parse()
+    .unwrap();
+    save::migrate(&mut table, TEST_VERSION);
+    assert_eq!(table["version"].as_integer(), Some(VERSION as i64));
+    assert!(!table.contains_key("scores"));
+    let data: SaveData = toml::Value::Table(table).try_into().unwrap();
+    assert_eq!(data.high_scores, [HighScore { seed: 0, ..score(4, "2024-01-02") }]);
+
+    // a current table is left as it was
+    let mut current: toml::Table = SaveData::new().to_toml().parse().unwrap();
+    let before = current.clone();
+    save::migrate(&mut current, VERSION);
+    assert_eq!(current, before);
+}
+
+#[test]
+fn a_missing_save_opens_empty() {
+    let path = scratch("missing").join("save.toml");
+    let save = SaveFile::open(&path);
+    assert_eq!(save.data, SaveData::new());
+    assert!(!path.exists(), "opening doesn't write anything");
+}
+
+#[test]
+fn a_corrupt_save_is_moved_aside() {
+    let dir = scratch("corrupt");
+    let path = dir.join("save.toml");
+    fs::write(&path, "this is not a save").unwrap();
+    let save = SaveFile::open(&path);
+    assert_eq!(save.data, SaveData::new());
+    assert!(!path.exists());
+    assert_eq!(fs::read_to_string(dir.join("save.toml.corrupt")).unwrap(), "this is not a save");
+
+    // and the next save doesn't touch the backup
+    save.save().unwrap();
+    assert!(path.exists());
+    assert_eq!(fs::read_to_string(dir.join("save.toml.corrupt")).unwrap(), "this is not a save");
+}
+
+#[test]
+fn saving_replaces_the_file_through_a_temporary() {
+    let dir = scratch("atomic").join("nested");
+    let path = dir.join("save.toml");
+    let mut save = SaveFile::new(&path);
+    save.save().unwrap();
+    assert_eq!(SaveFile::open(&path).data, SaveData::new());
+
+    // left behind by a write that never finished
+    fs::write(dir.join("save.toml.tmp"), "half a sa").unwrap();
+    assert_eq!(save.record(42, 7), Some(0));
+    assert!(!dir.join("save.toml.tmp").exists(), "the temporary should be renamed over the save");
+    let reopened = SaveFile::open(&path);
+    assert_eq!(reopened.data, save.data);
+    assert_eq!(reopened.data.best().map(|s| (s.score, s.seed)), Some((42, 7)));
+    let entries = fs::read_dir(&dir).unwrap().count();
+    assert_eq!(entries, 1, "only the save itself should be left");
+}
+
+#[test]
+fn a_new_save_ignores_what_is_on_disk() {
+    let path = scratch("new").join("save.toml");
+    fs::write(&path, format!("version = {}\n[settings]\nfullscreen = true\n", VERSION)).unwrap();
+    assert!(SaveFile::open(&path).data.settings.fullscreen);
+    assert_eq!(SaveFile::new(&path).data, SaveData::new());
+}
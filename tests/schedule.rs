@@ -0,0 +1,175 @@
+use flappy::scene::Scene;
+use flappy::schedule::{Schedule, Stage};
+use flappy::state::{self, GameState, State};
+
+/// Resource the systems under test write their names to as they run.
+#[derive(Debug, Default)]
+struct Log(Vec<&'static str>);
+
+fn log(name: &'static str) -> impl FnMut(&mut Scene) {
+    move |scene| scene.resource_mut::<Log>().unwrap().0.push(name)
+}
+
+fn scene() -> Scene {
+    let mut scene = Scene::new();
+    scene.insert_resource(Log::default());
+    scene
+}
+
+/// Names logged so far, clearing the log.
+fn take_log(scene: &mut Scene) -> Vec<&'static str> {
+    std::mem::take(&mut scene.resource_mut::<Log>().unwrap().0)
+}
+
+#[test]
+fn stages_run_in_order() {
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Render, "render", log("render"));
+    schedule.add_system(Stage::Update, "update", log("update"));
+    schedule.add_system(Stage::Input, "input", log("input"));
+    schedule.add_system(Stage::LateUpdate, "late", log("late"));
+    schedule.add_system(Stage::FixedUpdate, "fixed", log("fixed"));
+    let mut scene = scene();
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["input", "fixed", "update", "late", "render"]);
+}
+
+#[test]
+fn systems_keep_the_order_they_were_added_in_unless_constrained() {
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, "a", log("a"));
+    schedule.add_system(Stage::Update, "b", log("b"));
+    schedule.add_system(Stage::Update, "c", log("c"));
+    assert_eq!(schedule.system_order(Stage::Update), ["a", "b", "c"]);
+}
+
+#[test]
+fn before_and_after_reorder_systems() {
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, "draw", log("draw")).after("move");
+    schedule.add_system(Stage::Update, "move", log("move")).after("input");
+    schedule.add_system(Stage::Update, "input", log("input"));
+    schedule.add_system(Stage::Update, "clear", log("clear")).before("input");
+    assert_eq!(schedule.system_order(Stage::Update), ["clear", "input", "move", "draw"]);
+
+    let mut scene = scene();
+    schedule.run_stage(Stage::Update, &mut scene);
+    assert_eq!(take_log(&mut scene), ["clear", "input", "move", "draw"]);
+}
+
+#[test]
+#[should_panic(expected = "ordered against unknown system `missing`")]
+fn ordering_against_an_unknown_system_panics() {
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, "a", log("a")).after("missing");
+    schedule.system_order(Stage::Update);
+}
+
+#[test]
+#[should_panic(expected = "ordering cycle")]
+fn ordering_cycles_panic() {
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, "a", log("a")).after("b");
+    schedule.add_system(Stage::Update, "b", log("b")).after("c");
+    schedule.add_system(Stage::Update, "c", log("c")).after("a");
+    schedule.system_order(Stage::Update);
+}
+
+#[test]
+#[should_panic(expected = "registered twice")]
+fn names_are_unique_within_a_stage() {
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, "a", log("a"));
+    schedule.add_system(Stage::Update, "a", log("a"));
+}
+
+#[test]
+fn run_conditions_must_all_hold() {
+    #[derive(Debug)]
+    struct Paused;
+    #[derive(Debug)]
+    struct Muted;
+
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(Stage::Update, "play", log("play"))
+        .run_if(|scene| !scene.has_resource::<Paused>())
+        .run_if(|scene| !scene.has_resource::<Muted>());
+    schedule.add_system(Stage::Update, "always", log("always"));
+    let mut scene = scene();
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["play", "always"]);
+    scene.insert_resource(Muted);
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["always"]);
+    scene.remove_resource::<Muted>();
+    scene.insert_resource(Paused);
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["always"]);
+}
+
+#[test]
+fn state_gated_systems_only_run_in_their_states() {
+    use GameState::*;
+
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, "flap", log("flap")).in_state(Playing);
+    schedule
+        .add_system(Stage::Update, "tap", log("tap"))
+        .in_states(&[Title, GameOver]);
+    let mut scene = scene();
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), [] as [&str; 0], "no State resource, so no state holds");
+
+    scene.insert_resource(State::new(Title));
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["tap"]);
+    state::set_state(&mut scene, Playing);
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["flap"]);
+}
+
+#[test]
+fn transitions_run_exit_then_enter_hooks_between_stages() {
+    use GameState::*;
+
+    let mut schedule = Schedule::new();
+    schedule.on_enter(Title, "enter_title", log("enter_title"));
+    schedule.on_exit(Title, "exit_title", log("exit_title"));
+    schedule.on_enter(Playing, "enter_playing", log("enter_playing"));
+    schedule.on_enter(Playing, "enter_playing_2", log("enter_playing_2"));
+    schedule.add_system(Stage::Input, "start", |scene| {
+        log("start")(scene);
+        state::set_state(scene, Playing);
+    });
+    schedule.add_system(Stage::Update, "update", log("update"));
+
+    let mut scene = scene();
+    scene.insert_resource(State::new(Title));
+    schedule.run(&mut scene);
+    assert_eq!(
+        take_log(&mut scene),
+        ["enter_title", "start", "exit_title", "enter_playing", "enter_playing_2", "update"]
+    );
+    assert_eq!(state::current_state(&scene), Some(Playing));
+
+    // asking for the state already current changes nothing
+    schedule.run(&mut scene);
+    assert_eq!(take_log(&mut scene), ["start", "update"]);
+}
+
+#[test]
+fn hooks_may_request_a_further_transition() {
+    use GameState::*;
+
+    let mut schedule = Schedule::new();
+    schedule.on_enter(Dying, "skip_dying", |scene| state::set_state(scene, GameOver));
+    schedule.on_exit(Dying, "exit_dying", log("exit_dying"));
+    schedule.on_enter(GameOver, "enter_game_over", log("enter_game_over"));
+    let mut scene = scene();
+    scene.insert_resource(State::new(Playing));
+    state::set_state(&mut scene, Dying);
+    schedule.apply_transitions(&mut scene);
+    assert_eq!(take_log(&mut scene), ["exit_dying", "enter_game_over"]);
+    assert_eq!(state::current_state(&scene), Some(GameOver));
+}
@@ -0,0 +1,133 @@
+use flappy::camera::Camera;
+use flappy::components::Mesh;
+use flappy::difficulty::Difficulty;
+use flappy::entity::Entity;
+use flappy::game::{self, Pipe, Player, PIPE_WIDTH};
+use flappy::scene::Scene;
+use flappy::score::{self, Score, ScoreHud, Scored};
+use nalgebra::{Point3, Translation3, Vector4};
+
+/// A round with the bird at `bird_x` and one pipe pair with its left edge at
+/// x = 0.
+fn round(bird_x: f32) -> (Scene, Entity, Entity) {
+    let mut scene = Scene::new();
+    scene.insert_resource(Score::new());
+    let bird = scene.spawn();
+    let mut mesh = game::bird_mesh();
+    mesh.translation = Translation3::new(bird_x, 0.0, 0.0);
+    bird.add_mesh(&mut scene, mesh);
+    bird.insert(&mut scene, Player);
+    let pipe = scene.spawn();
+    pipe.add_mesh(&mut scene, game::pipe_pair_mesh(Difficulty::new().gap.sample(0.0)));
+    pipe.insert(&mut scene, Pipe);
+    (scene, bird, pipe)
+}
+
+fn score(scene: &Scene) -> u32 {
+    scene.resource::<Score>().unwrap().value
+}
+
+fn move_bird(scene: &mut Scene, bird: Entity, x: f32) {
+    bird.get_mut::<Mesh>(scene).unwrap().translation.x = x;
+}
+
+#[test]
+fn a_pair_counts_once_its_trailing_edge_is_passed() {
+    let (mut scene, bird, pipe) = round(-10.0);
+    score::score_pipes(&mut scene);
+    assert_eq!(score(&scene), 0);
+
+    // between the pipes' leading and trailing edges
+    move_bird(&mut scene, bird, PIPE_WIDTH / 2.0);
+    score::score_pipes(&mut scene);
+    assert_eq!(score(&scene), 0);
+
+    move_bird(&mut scene, bird, PIPE_WIDTH + 1.0);
+    score::score_pipes(&mut scene);
+    assert_eq!(score(&scene), 1);
+    assert!(pipe.get::<Scored>(&scene).is_some());
+    for _ in 0..3 {
+        score::score_pipes(&mut scene);
+    }
+    assert_eq!(score(&scene), 1);
+}
+
+#[test]
+fn scored_pairs_are_not_counted_again() {
+    let (mut scene, _, pipe) = round(PIPE_WIDTH + 1.0);
+    pipe.insert(&mut scene, Scored);
+    score::score_pipes(&mut scene);
+    assert_eq!(score(&scene), 0);
+}
+
+#[test]
+fn digits_are_a_quad_each_from_the_atlas() {
+    let mesh = score::digits_mesh(2024);
+    assert_eq!(mesh.verts().len(), 4 * 4);
+    assert_eq!(mesh.elements().len(), 4 * 6);
+    for (quad, digit) in mesh.verts().chunks(4).zip(["2", "0", "2", "4"]) {
+        let region = game::sprite(&format!("digit_{}", digit));
+        let uvs: Vec<_> = quad.iter().map(|vert| vert.uv).collect();
+        assert_eq!(uvs, region.uvs());
+    }
+    // laid out left to right about the origin
+    let xs: Vec<f32> = mesh.verts().iter().map(|vert| vert.pos.x).collect();
+    let (left, right) = xs.iter().fold((f32::MAX, f32::MIN), |(l, r), &x| (l.min(x), r.max(x)));
+    assert!((left + right).abs() < 1e-4, "spans {} to {}", left, right);
+    assert!(mesh.verts()[0].pos.x < mesh.verts()[4].pos.x);
+}
+
+/// Where the HUD's left and right edges land on screen, from -1 to 1.
+fn hud_on_screen(scene: &Scene) -> (f32, f32) {
+    let camera = scene.resource::<Camera>().unwrap();
+    let transform = camera.perspective() * camera.view();
+    let mesh = scene.query_ref::<&Mesh>().with::<ScoreHud>().next().unwrap();
+    let model = mesh.model();
+    mesh.verts()
+        .iter()
+        .map(|vert| {
+            let clip = transform * model * Vector4::new(vert.pos.x, vert.pos.y, vert.pos.z, 1.0);
+            clip.x / clip.w
+        })
+        .fold((f32::MAX, f32::MIN), |(l, r), x| (l.min(x), r.max(x)))
+}
+
+#[test]
+fn the_hud_stays_centred_when_the_window_is_resized() {
+    let mut scene = Scene::new();
+    scene.insert_resource(Score { value: 17 });
+    let mut camera = Camera::new(Point3::new(0.0, 3.0, 0.0), Point3::new(0.0, 0.0, 0.0));
+    camera.resize(640, 480);
+    scene.insert_resource(camera);
+    score::update_score_hud(&mut scene);
+    let (left, right) = hud_on_screen(&scene);
+    assert!((left + right).abs() < 1e-4, "spans {} to {}", left, right);
+    let top = scene.query::<&Mesh>().with::<ScoreHud>().next().unwrap().translation.z;
+
+    scene.resource_mut::<Camera>().unwrap().resize(1920, 480);
+    score::update_score_hud(&mut scene);
+    let (left, right) = hud_on_screen(&scene);
+    assert!((left + right).abs() < 1e-4, "spans {} to {}", left, right);
+
+    scene.resource_mut::<Camera>().unwrap().resize(1920, 1080);
+    score::update_score_hud(&mut scene);
+    let moved = scene.query::<&Mesh>().with::<ScoreHud>().next().unwrap().translation.z;
+    assert!(moved > top, "the HUD should follow the top of a taller window");
+}
+
+#[test]
+fn the_hud_is_rebuilt_only_when_the_score_changes() {
+    let mut scene = Scene::new();
+    scene.insert_resource(Score::new());
+    score::update_score_hud(&mut scene);
+    let generation = |scene: &mut Scene| scene.query::<&Mesh>().with::<ScoreHud>().next().unwrap().generation();
+    let first = generation(&mut scene);
+    score::update_score_hud(&mut scene);
+    assert_eq!(generation(&mut scene), first);
+
+    scene.resource_mut::<Score>().unwrap().value = 1;
+    score::update_score_hud(&mut scene);
+    assert_ne!(generation(&mut scene), first);
+    assert_eq!(scene.query::<&ScoreHud>().next().unwrap().value, 1);
+    assert_eq!(scene.query::<&Mesh>().with::<ScoreHud>().count(), 1);
+}
@@ -0,0 +1,274 @@
+use flappy::image::Image;
+use flappy::renderer::primatives::Vert;
+use flappy::renderer::backend::{
+    Backend, BufferData, BufferKind, DrawCall, DrawState, Frame, Geometry, Pass, PipelineDesc, PipelineId, TargetId,
+    TextureId, VertexLayout,
+};
+use flappy::renderer::software::SoftwareRenderer;
+use flappy::renderer::sprites::SpriteInstance;
+use flappy::renderer::target::TargetDesc;
+use nalgebra::{Matrix4, Point2, Point3, Vector3};
+
+const CLEAR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// A 2x2 texture: red and green on the top row, blue and a see-through white
+/// below.
+fn texture() -> Image {
+    Image::new(
+        2,
+        2,
+        vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 0],
+    )
+}
+
+fn solid(rgba: [u8; 4]) -> Image {
+    Image::new(1, 1, rgba.to_vec())
+}
+
+/// A quad covering clip space from `min` to `max` at depth `z`, with uvs from
+/// 0 to 1.
+fn quad(min: (f32, f32), max: (f32, f32), z: f32) -> [Vert; 4] {
+    let vert = |x: f32, y: f32, u: f32, v: f32| {
+        Vert::new(
+            Point3::new(x, y, z),
+            Point3::new(1.0, 1.0, 1.0),
+            Point2::new(u, v),
+            Point3::new(0.0, 0.0, 0.0),
+        )
+    };
+    [
+        vert(min.0, min.1, 0.0, 0.0),
+        vert(max.0, min.1, 1.0, 0.0),
+        vert(max.0, max.1, 1.0, 1.0),
+        vert(min.0, max.1, 0.0, 1.0),
+    ]
+}
+
+const QUAD: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+fn full_screen(z: f32) -> [Vert; 4] {
+    quad((-1.0, -1.0), (1.0, 1.0), z)
+}
+
+#[test]
+fn clear_fills_the_buffer() {
+    let mut renderer = SoftwareRenderer::new(3, 2);
+    renderer.clear([0.2, 0.3, 0.3, 1.0]);
+    let image = renderer.image();
+    assert!(image.pixels.chunks(4).all(|p| p == [51, 77, 77, 255]));
+}
+
+#[test]
+fn texels_are_nearest_with_the_top_row_at_v_zero() {
+    let mut renderer = SoftwareRenderer::new(4, 4);
+    renderer.clear(CLEAR);
+    renderer.draw(&full_screen(0.0), &QUAD, &Matrix4::identity(), &texture(), [1.0; 4], DrawState::OPAQUE);
+    let image = renderer.image();
+    // uv (0, 0) is at the bottom left of the screen and samples the top left
+    // texel, as GL does with a texture uploaded top row first
+    assert_eq!(image.pixel(0, 3), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(1, 2), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(3, 3), [0, 255, 0, 255]);
+    assert_eq!(image.pixel(0, 0), [0, 0, 255, 255]);
+}
+
+#[test]
+fn uvs_repeat_outside_zero_to_one() {
+    let mut renderer = SoftwareRenderer::new(4, 4);
+    renderer.clear(CLEAR);
+    let mut verts = full_screen(0.0);
+    for vert in &mut verts {
+        vert.uv.x += 1.0;
+    }
+    renderer.draw(&verts, &QUAD, &Matrix4::identity(), &texture(), [1.0; 4], DrawState::OPAQUE);
+    assert_eq!(renderer.image().pixel(0, 3), [255, 0, 0, 255]);
+}
+
+#[test]
+fn nearly_transparent_texels_are_discarded() {
+    let mut renderer = SoftwareRenderer::new(4, 4);
+    renderer.clear(CLEAR);
+    renderer.draw(&full_screen(0.0), &QUAD, &Matrix4::identity(), &texture(), [1.0; 4], DrawState::OPAQUE);
+    // the see-through white texel leaves the clear colour, even unblended
+    assert_eq!(renderer.image().pixel(3, 0), [0, 0, 0, 255]);
+}
+
+#[test]
+fn blending_mixes_by_source_alpha() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    renderer.clear([0.0, 0.0, 1.0, 1.0]);
+    let half_red = solid([255, 0, 0, 128]);
+    renderer.draw(&full_screen(0.0), &QUAD, &Matrix4::identity(), &half_red, [1.0; 4], DrawState::TRANSPARENT);
+    let [r, g, b, _] = renderer.image().pixel(0, 0);
+    assert_eq!((r, g), (128, 0));
+    assert_eq!(b, 127);
+}
+
+#[test]
+fn tint_multiplies_the_texel() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    renderer.clear(CLEAR);
+    let white = solid([255, 255, 255, 255]);
+    renderer.draw(&full_screen(0.0), &QUAD, &Matrix4::identity(), &white, [1.0, 0.5, 0.0, 1.0], DrawState::OPAQUE);
+    assert_eq!(renderer.image().pixel(1, 1), [255, 128, 0, 255]);
+}
+
+#[test]
+fn depth_test_keeps_the_nearest_fragment() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    renderer.clear(CLEAR);
+    let (red, green) = (solid([255, 0, 0, 255]), solid([0, 255, 0, 255]));
+    renderer.draw(&full_screen(-0.5), &QUAD, &Matrix4::identity(), &red, [1.0; 4], DrawState::OPAQUE);
+    renderer.draw(&full_screen(0.5), &QUAD, &Matrix4::identity(), &green, [1.0; 4], DrawState::OPAQUE);
+    assert_eq!(renderer.image().pixel(0, 0), [255, 0, 0, 255]);
+
+    // without depth writes the blended pass can't hide what comes after it
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    renderer.clear(CLEAR);
+    renderer.draw(&full_screen(-0.5), &QUAD, &Matrix4::identity(), &red, [1.0; 4], DrawState::TRANSPARENT);
+    renderer.draw(&full_screen(0.5), &QUAD, &Matrix4::identity(), &green, [1.0; 4], DrawState::OPAQUE);
+    assert_eq!(renderer.image().pixel(0, 0), [0, 255, 0, 255]);
+}
+
+#[test]
+fn fragments_outside_the_depth_range_are_clipped() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    renderer.clear(CLEAR);
+    let red = solid([255, 0, 0, 255]);
+    renderer.draw(&full_screen(1.5), &QUAD, &Matrix4::identity(), &red, [1.0; 4], DrawState::OPAQUE);
+    assert_eq!(renderer.image().pixel(0, 0), [0, 0, 0, 255]);
+}
+
+#[test]
+fn shared_edges_are_drawn_once() {
+    let mut renderer = SoftwareRenderer::new(8, 8);
+    renderer.clear(CLEAR);
+    let half_white = solid([255, 255, 255, 128]);
+    renderer.draw(&full_screen(0.0), &QUAD, &Matrix4::identity(), &half_white, [1.0; 4], DrawState::TRANSPARENT);
+    let image = renderer.image();
+    // pixels on the diagonal would be blended twice, and brighter, otherwise
+    let first = image.pixel(0, 0);
+    for y in 0..8 {
+        for x in 0..8 {
+            assert_eq!(image.pixel(x, y), first, "pixel ({}, {})", x, y);
+        }
+    }
+}
+
+#[test]
+fn pixel_centres_decide_coverage() {
+    let mut renderer = SoftwareRenderer::new(4, 1);
+    renderer.clear(CLEAR);
+    let red = solid([255, 0, 0, 255]);
+    // covers x from 0.5 to 2.5 pixels: the centres of pixels 0 and 2 lie on
+    // its left and right edges, and only the left edge owns its pixels
+    let verts = quad((-0.75, -1.0), (0.25, 1.0), 0.0);
+    renderer.draw(&verts, &QUAD, &Matrix4::identity(), &red, [1.0; 4], DrawState::OPAQUE);
+    let image = renderer.image();
+    let covered: Vec<bool> = (0..4).map(|x| image.pixel(x, 0) == [255, 0, 0, 255]).collect();
+    assert_eq!(covered, [true, true, false, false]);
+}
+
+/// A frame of one pass drawing `draws` with `state`, where view and
+/// projection leave clip space as it is.
+fn frame(target: Option<TargetId>, clear_color: [f32; 4], state: DrawState, draws: Vec<DrawCall>) -> Frame {
+    Frame {
+        target,
+        clear_color,
+        view: Matrix4::identity(),
+        projection: Matrix4::identity(),
+        passes: vec![Pass { state, draws }],
+    }
+}
+
+fn draw_call(pipeline: PipelineId, texture: TextureId, geometry: Geometry) -> DrawCall {
+    DrawCall {
+        pipeline,
+        texture,
+        model: Matrix4::identity(),
+        geometry,
+        uniforms: Vec::new(),
+    }
+}
+
+#[test]
+fn indexed_draws_read_the_elements_they_name() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    let pipeline = renderer.create_pipeline(&PipelineDesc::new("hello.vs", "hello.fs", VertexLayout::Mesh));
+    let red = renderer.create_texture(&solid([255, 0, 0, 255]));
+    let vertices = renderer.create_buffer(BufferKind::Vertex);
+    let elements = renderer.create_buffer(BufferKind::Element);
+    renderer.update_buffer(vertices, BufferData::Vertices(&full_screen(0.0)));
+    renderer.update_buffer(elements, BufferData::Elements(&QUAD));
+    // only the triangle below the diagonal from bottom left to top right
+    let geometry = Geometry::Indexed { vertices, elements, first: 0, count: 3 };
+    renderer.submit(&frame(None, CLEAR, DrawState::OPAQUE, vec![draw_call(pipeline, red, geometry)]));
+    let image = renderer.image();
+    assert_eq!(image.pixel(1, 1), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(0, 0), [0, 0, 0, 255]);
+}
+
+#[test]
+fn instances_are_tinted_quads() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    let pipeline = renderer.create_pipeline(&PipelineDesc::new("sprite.vs", "sprite.fs", VertexLayout::Sprite));
+    let white = renderer.create_texture(&solid([255, 255, 255, 255]));
+    let instances = renderer.create_buffer(BufferKind::Instance);
+    // the unit quad lies on x/z, so turn it to face the screen and fill it
+    let instance = SpriteInstance {
+        model: Matrix4::new_nonuniform_scaling(&Vector3::new(2.0, 2.0, 1.0))
+            * Matrix4::from_axis_angle(&Vector3::x_axis(), -std::f32::consts::FRAC_PI_2),
+        uv_min: Point2::new(0.0, 0.0),
+        uv_max: Point2::new(1.0, 1.0),
+        tint: [0.0, 1.0, 0.0, 1.0],
+    };
+    renderer.update_buffer(instances, BufferData::Instances(&[instance]));
+    let geometry = Geometry::Instanced { instances, first: 0, count: 1 };
+    renderer.submit(&frame(None, CLEAR, DrawState::OPAQUE, vec![draw_call(pipeline, white, geometry)]));
+    let image = renderer.image();
+    assert!(image.pixels.chunks(4).all(|p| p == [0, 255, 0, 255]));
+}
+
+#[test]
+fn full_screen_draws_copy_a_target_the_right_way_up() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    let target = renderer.create_target(&TargetDesc::new(2, 2)).unwrap();
+    let mesh = renderer.create_pipeline(&PipelineDesc::new("hello.vs", "hello.fs", VertexLayout::Mesh));
+    let copy = renderer.create_pipeline(&PipelineDesc::new("fullscreen.vs", "copy.fs", VertexLayout::FullScreen));
+    let green = renderer.create_texture(&solid([0, 255, 0, 255]));
+    let vertices = renderer.create_buffer(BufferKind::Vertex);
+    let elements = renderer.create_buffer(BufferKind::Element);
+    renderer.update_buffer(vertices, BufferData::Vertices(&quad((-1.0, -1.0), (1.0, 0.0), 0.0)));
+    renderer.update_buffer(elements, BufferData::Elements(&QUAD));
+
+    // green over the bottom half of a red target, then the target on screen
+    let geometry = Geometry::Indexed { vertices, elements, first: 0, count: 6 };
+    let red = [1.0, 0.0, 0.0, 1.0];
+    renderer.submit(&frame(Some(target), red, DrawState::OPAQUE, vec![draw_call(mesh, green, geometry)]));
+    let texture = renderer.target_texture(target);
+    renderer.submit(&frame(None, CLEAR, DrawState::OVERWRITE, vec![draw_call(copy, texture, Geometry::FullScreen)]));
+    assert_eq!(renderer.image(), renderer.read_target(target));
+    let image = renderer.image();
+    assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+    assert_eq!(image.pixel(0, 1), [0, 255, 0, 255]);
+}
+
+#[test]
+fn targets_without_depth_keep_every_fragment() {
+    let mut renderer = SoftwareRenderer::new(2, 2);
+    let target = renderer.create_target(&TargetDesc::new(2, 2)).unwrap();
+    let pipeline = renderer.create_pipeline(&PipelineDesc::new("hello.vs", "hello.fs", VertexLayout::Mesh));
+    let (red, green) = (
+        renderer.create_texture(&solid([255, 0, 0, 255])),
+        renderer.create_texture(&solid([0, 255, 0, 255])),
+    );
+    let buffers = [(-0.5, red), (0.5, green)].map(|(z, texture)| {
+        let vertices = renderer.create_buffer(BufferKind::Vertex);
+        let elements = renderer.create_buffer(BufferKind::Element);
+        renderer.update_buffer(vertices, BufferData::Vertices(&full_screen(z)));
+        renderer.update_buffer(elements, BufferData::Elements(&QUAD));
+        draw_call(pipeline, texture, Geometry::Indexed { vertices, elements, first: 0, count: 6 })
+    });
+    renderer.submit(&frame(Some(target), CLEAR, DrawState::OPAQUE, buffers.to_vec()));
+    assert_eq!(renderer.read_target(target).pixel(0, 0), [0, 255, 0, 255]);
+}
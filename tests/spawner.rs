@@ -0,0 +1,64 @@
+use flappy::components::Mesh;
+use flappy::game::Pipe;
+use flappy::physics;
+use flappy::scene::Scene;
+use flappy::spawner::{self, PipeSpawner};
+use flappy::time::Time;
+use std::time::Duration;
+
+/// Where each pipe pair is after `seconds` of scrolling a course from `spawner`,
+/// left to right.
+fn course(spawner: PipeSpawner, seconds: u32) -> Vec<(f32, f32)> {
+    let mut scene = Scene::new();
+    scene.insert_resource(spawner);
+    let mut time = Time::new();
+    for _ in 0..seconds * 60 {
+        time.advance(Duration::from_secs(1) / 60);
+        scene.insert_resource(time);
+        physics::integrate(&mut scene);
+        spawner::spawn_pipes(&mut scene);
+    }
+    let mut pipes: Vec<(f32, f32)> = scene
+        .query::<&Mesh>()
+        .with::<Pipe>()
+        .map(|mesh| (mesh.translation.x, mesh.translation.z))
+        .collect();
+    pipes.sort_by(|a, b| a.0.total_cmp(&b.0));
+    pipes
+}
+
+#[test]
+fn a_seed_always_lays_out_the_same_course() {
+    let first = course(PipeSpawner::new(7), 20);
+    assert!(first.len() > 3, "expected several pairs on screen, got {:?}", first);
+    assert_eq!(first, course(PipeSpawner::new(7), 20));
+    assert_ne!(first, course(PipeSpawner::new(8), 20));
+}
+
+#[test]
+fn reset_replays_the_course() {
+    let mut spawner = PipeSpawner::new(7);
+    let gaps: Vec<f32> = (0..10).map(|_| spawner.next_gap_center()).collect();
+    spawner.reset();
+    assert_eq!(gaps, (0..10).map(|_| spawner.next_gap_center()).collect::<Vec<_>>());
+    spawner.reseed(8);
+    assert_ne!(gaps, (0..10).map(|_| spawner.next_gap_center()).collect::<Vec<_>>());
+}
+
+#[test]
+fn pairs_are_spaced_evenly_and_their_gaps_stay_in_range() {
+    let spawner = PipeSpawner::new(3);
+    let (spacing, (low, high)) = (spawner.spacing, spawner.gap_center);
+    let pipes = course(spawner, 20);
+    for pair in pipes.windows(2) {
+        assert!((pair[1].0 - pair[0].0 - spacing).abs() < 0.01, "{:?}", pair);
+    }
+    assert!(pipes.iter().all(|&(_, z)| (low..=high).contains(&z)));
+}
+
+#[test]
+fn no_spacing_spawns_nothing() {
+    let mut spawner = PipeSpawner::new(7);
+    spawner.spacing = 0.0;
+    assert!(course(spawner, 1).is_empty());
+}
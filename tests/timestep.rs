@@ -0,0 +1,46 @@
+use flappy::config::{ConfigError, GameConfig};
+use flappy::timestep::{FixedTimestep, TimestepError};
+use std::time::Duration;
+
+#[test]
+fn a_zero_tick_rate_is_an_error() {
+    assert_eq!(FixedTimestep::new(0).unwrap_err(), TimestepError::ZeroRate);
+    let mut fixed = FixedTimestep::new(60).unwrap();
+    assert_eq!(fixed.set_tick_rate(0), Err(TimestepError::ZeroRate));
+    assert_eq!(fixed.step, Duration::from_secs(1) / 60, "a bad rate leaves the step alone");
+}
+
+#[test]
+fn frames_are_spent_in_whole_ticks() {
+    let mut fixed = FixedTimestep::new(100).unwrap();
+    fixed.accumulate(Duration::from_millis(25));
+    let mut ticks = 0;
+    while fixed.expend() {
+        ticks += 1;
+    }
+    fixed.update_alpha();
+    assert_eq!(ticks, 2);
+    assert_eq!(fixed.elapsed(), Duration::from_millis(20));
+    assert!((fixed.alpha() - 0.5).abs() < 1e-4);
+}
+
+#[test]
+fn changing_the_rate_keeps_time_banked() {
+    let mut fixed = FixedTimestep::new(100).unwrap();
+    fixed.accumulate(Duration::from_millis(15));
+    assert!(fixed.expend());
+    fixed.set_tick_rate(200).unwrap();
+    assert!(fixed.expend());
+    assert!(!fixed.expend());
+    assert_eq!(fixed.elapsed(), Duration::from_millis(15));
+}
+
+#[test]
+fn config_sets_the_tick_rate() {
+    let config = GameConfig::parse("[timestep]\ntick_rate = 240\nmax_steps = 4\n").unwrap();
+    assert_eq!((config.timestep.tick_rate, config.timestep.max_steps), (240, 4));
+    let Err(ConfigError::Invalid(problems)) = GameConfig::parse("[timestep]\ntick_rate = 0\nmax_steps = 0\n") else {
+        panic!("a zero tick rate should be rejected");
+    };
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+}
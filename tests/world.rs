@@ -0,0 +1,177 @@
+use flappy::entity::Entity;
+use flappy::world::World;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Position(f32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Speed(f32);
+
+#[derive(Debug)]
+struct Frozen;
+
+#[derive(Debug, PartialEq)]
+struct Gravity(f32);
+
+#[test]
+fn stale_handles_are_rejected_once_their_slot_is_reused() {
+    let mut world = World::new();
+    let old = world.spawn();
+    world.insert(old, Position(1.0));
+    assert!(world.despawn(old));
+    assert!(!world.despawn(old));
+
+    let new = world.spawn();
+    assert_eq!(new.index(), old.index(), "the freed slot should be reused");
+    assert_ne!(new, old);
+    assert!(!world.is_alive(old));
+    assert!(world.is_alive(new));
+    assert_eq!(world.get::<Position>(new), None, "components go with the entity");
+
+    world.insert(new, Position(2.0));
+    assert_eq!(world.insert(old, Position(3.0)), None);
+    assert_eq!(world.get::<Position>(old), None);
+    assert_eq!(world.get::<Position>(new), Some(&Position(2.0)));
+    assert_eq!(world.remove::<Position>(old), None);
+    assert_eq!(world.query_one::<&Position>(old), None);
+    assert_eq!(world.entities().collect::<Vec<_>>(), [new]);
+}
+
+#[test]
+fn removing_a_component_keeps_the_others_reachable() {
+    let mut world = World::new();
+    let entities: Vec<Entity> = (0..3).map(|_| world.spawn()).collect();
+    for (i, entity) in entities.iter().enumerate() {
+        world.insert(*entity, Position(i as f32));
+    }
+    assert_eq!(world.remove::<Position>(entities[0]), Some(Position(0.0)));
+    assert_eq!(world.get::<Position>(entities[1]), Some(&Position(1.0)));
+    assert_eq!(world.get::<Position>(entities[2]), Some(&Position(2.0)));
+    assert_eq!(world.values::<Position>().len(), 2);
+}
+
+#[test]
+#[should_panic(expected = "mutably and more than once")]
+fn queries_that_alias_a_component_panic() {
+    let mut world = World::new();
+    let _ = world.query::<(&mut Position, &Position)>();
+}
+
+#[test]
+#[should_panic(expected = "mutably and more than once")]
+fn optional_components_count_towards_aliasing() {
+    let mut world = World::new();
+    let _ = world.query::<(&Position, Option<&mut Position>)>();
+}
+
+#[test]
+fn reading_a_component_twice_is_allowed() {
+    let mut world = World::new();
+    let entity = world.spawn();
+    world.insert(entity, Position(1.0));
+    let items: Vec<_> = world.query::<(&Position, &Position)>().collect();
+    assert_eq!(items, [(&Position(1.0), &Position(1.0))]);
+}
+
+#[test]
+fn optional_components_match_entities_without_them() {
+    let mut world = World::new();
+    let moving = world.spawn();
+    let still = world.spawn();
+    world.insert(moving, Position(0.0));
+    world.insert(moving, Speed(2.0));
+    world.insert(still, Position(5.0));
+
+    for (position, speed) in world.query::<(&mut Position, Option<&Speed>)>() {
+        position.0 += speed.map_or(0.0, |s| s.0);
+    }
+    assert_eq!(world.get::<Position>(moving), Some(&Position(2.0)));
+    assert_eq!(world.get::<Position>(still), Some(&Position(5.0)));
+
+    let mut found: Vec<_> = world.query::<(Entity, Option<&Speed>)>().collect();
+    found.sort_by_key(|(entity, _)| entity.index());
+    assert_eq!(found, [(moving, Some(&Speed(2.0))), (still, None)]);
+}
+
+#[test]
+fn a_component_type_never_inserted_matches_nothing() {
+    let mut world = World::new();
+    world.spawn();
+    assert_eq!(world.query::<&Speed>().count(), 0);
+    assert_eq!(world.query::<Entity>().with::<Speed>().count(), 0);
+    assert_eq!(world.query::<Entity>().without::<Speed>().count(), 1);
+}
+
+#[test]
+fn with_and_without_filter_on_other_components() {
+    let mut world = World::new();
+    let frozen = world.spawn();
+    let free = world.spawn();
+    let bare = world.spawn();
+    for entity in [frozen, free, bare] {
+        world.insert(entity, Position(0.0));
+    }
+    world.insert(frozen, Frozen);
+    world.insert(free, Speed(1.0));
+
+    let with: Vec<Entity> = world.query::<Entity>().with::<Frozen>().collect();
+    assert_eq!(with, [frozen]);
+    let mut without: Vec<Entity> = world.query::<(Entity, &Position)>().without::<Frozen>().map(|(e, _)| e).collect();
+    without.sort_by_key(Entity::index);
+    assert_eq!(without, [free, bare]);
+    let both: Vec<Entity> = world.query::<Entity>().with::<Position>().with::<Speed>().without::<Frozen>().collect();
+    assert_eq!(both, [free]);
+}
+
+#[test]
+fn shared_queries_borrow_the_world_immutably() {
+    let mut world = World::new();
+    let moving = world.spawn();
+    let still = world.spawn();
+    world.insert(moving, Position(1.0));
+    world.insert(moving, Speed(2.0));
+    world.insert(still, Position(5.0));
+    world.insert(still, Frozen);
+
+    let world = &world;
+    // two at once, each holding on to what it reads
+    let mut positions: Vec<(Entity, &Position)> = world.query_ref::<(Entity, &Position)>().collect();
+    let speeds: Vec<(Entity, Option<&Speed>)> = world.query_ref::<(Entity, Option<&Speed>)>().collect();
+    positions.sort_by_key(|(entity, _)| entity.index());
+    assert_eq!(positions, [(moving, &Position(1.0)), (still, &Position(5.0))]);
+    assert!(speeds.contains(&(moving, Some(&Speed(2.0)))));
+    assert!(speeds.contains(&(still, None)));
+
+    let free: Vec<&Position> = world.query_ref::<&Position>().without::<Frozen>().collect();
+    assert_eq!(free, [&Position(1.0)]);
+    assert_eq!(world.query_ref::<Entity>().with::<Frozen>().collect::<Vec<_>>(), [still]);
+    assert_eq!(world.query_ref::<&Gravity>().count(), 0);
+}
+
+#[test]
+fn resources_are_inserted_replaced_and_removed() {
+    let mut world = World::new();
+    assert!(!world.has_resource::<Gravity>());
+    assert_eq!(world.insert_resource(Gravity(-9.8)), None);
+    assert_eq!(world.insert_resource(Gravity(-1.6)), Some(Gravity(-9.8)));
+    world.resource_mut::<Gravity>().unwrap().0 *= 2.0;
+    assert_eq!(world.resource::<Gravity>(), Some(&Gravity(-3.2)));
+
+    let doubled = world.resource_scope(|world, gravity: &mut Gravity| {
+        assert!(!world.has_resource::<Gravity>(), "taken out while in scope");
+        gravity.0 * 2.0
+    });
+    assert_eq!(doubled, -6.4);
+    assert!(world.has_resource::<Gravity>());
+
+    assert_eq!(world.remove_resource::<Gravity>(), Some(Gravity(-3.2)));
+    assert_eq!(world.remove_resource::<Gravity>(), None);
+    assert_eq!(world.resource::<Gravity>(), None);
+}
+
+#[test]
+#[should_panic(expected = "does not exist")]
+fn scoping_a_missing_resource_panics() {
+    let mut world = World::new();
+    world.resource_scope(|_, _: &mut Gravity| ());
+}